@@ -1,38 +1,35 @@
 extern crate multix;
 
+use multix::ThreadPool;
 use std::fs::File;
 use std::io::prelude::*;
 use std::net::TcpListener;
 use std::net::TcpStream;
 use std::thread;
 use std::time::Duration;
-use multix::ThreadPool;
 
 fn main() {
     let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
     let pool = ThreadPool::new(4);
 
-    let mut counter = 0;
-
-    for stream in listener.incoming() {
+    for (counter, stream) in listener.incoming().enumerate() {
         if counter == 2 {
             println!("Shutting down");
             break;
         }
 
-        counter += 1;
-
         let stream = stream.unwrap();
 
         pool.send(move || {
             handle_connection(stream);
-        }).unwrap();
+        })
+        .unwrap();
     }
 }
 
 fn handle_connection(mut stream: TcpStream) {
     let mut buffer = [0; 512];
-    stream.read(&mut buffer).unwrap();
+    let _ = stream.read(&mut buffer).unwrap();
 
     let get = b"GET / HTTP/1.1\r\n";
     let sleep = b"GET /sleep HTTP/1.1\r\n";
@@ -52,6 +49,6 @@ fn handle_connection(mut stream: TcpStream) {
 
     let response = format!("{}{}", status_line, contents);
 
-    stream.write(response.as_bytes()).unwrap();
+    stream.write_all(response.as_bytes()).unwrap();
     stream.flush().unwrap();
 }
@@ -0,0 +1,178 @@
+use std::fs;
+
+/// Which cores of a hybrid CPU a pool's workers run on, see
+/// `TPBuilder::prefer_cores`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum CoreKind {
+    /// Lets the OS place workers. This is the default.
+    #[default]
+    Any,
+    /// Keeps workers on the fastest cores, for latency sensitive pools.
+    Performance,
+    /// Keeps workers on the efficiency cores, so background pools leave the
+    /// performance cores to the latency path.
+    Efficiency,
+}
+
+/// How workers are pinned to CPUs, see `TPBuilder::pin_workers`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub enum Pinning {
+    /// Workers may run on any allowed CPU. This is the default.
+    #[default]
+    Unpinned,
+    /// Each worker gets a CPU of its own, in order, wrapping around when
+    /// there are more workers than CPUs.
+    PerCore,
+    /// Worker `i` is pinned to `cpus[i % cpus.len()]`.
+    Cpus(Vec<usize>),
+}
+
+/// The CPUs a pool's workers are placed on, or `None` to leave placement to
+/// the OS.
+pub fn placement(kind: CoreKind, pinning: &Pinning) -> Option<Vec<usize>> {
+    let cpus = match pinning {
+        Pinning::Cpus(cpus) => Some(cpus.clone()),
+        Pinning::PerCore => cores_of(kind).or_else(spread),
+        Pinning::Unpinned => cores_of(kind),
+    };
+
+    cpus.filter(|cpus| !cpus.is_empty())
+}
+
+// Every CPU the building thread may use, one per physical core first where
+// the topology is known.
+#[cfg(feature = "topology")]
+fn spread() -> Option<Vec<usize>> {
+    let mut cpus = crate::topology::Topology::detect().spread();
+
+    if let Some(allowed) = current() {
+        cpus.retain(|cpu| allowed.contains(cpu));
+    }
+
+    Some(cpus)
+}
+
+#[cfg(not(feature = "topology"))]
+fn spread() -> Option<Vec<usize>> {
+    current().or_else(|| Some((0..num_cpus::get()).collect()))
+}
+
+/// The CPUs of `kind`, or `None` if the CPU is not hybrid or its topology
+/// cannot be read. Intel hybrid parts list their core types under
+/// `/sys/devices/cpu_core` and `/sys/devices/cpu_atom`; other big.LITTLE
+/// designs report a lower `cpu_capacity` for their efficiency cores.
+pub fn cores_of(kind: CoreKind) -> Option<Vec<usize>> {
+    let (big, little) = match (read_cpus("cpu_core"), read_cpus("cpu_atom")) {
+        (Some(big), Some(little)) => (big, little),
+        _ => by_capacity()?,
+    };
+
+    match kind {
+        CoreKind::Any => None,
+        CoreKind::Performance => Some(big),
+        CoreKind::Efficiency => Some(little),
+    }
+}
+
+/// Restricts the calling thread to `cpus`. Returns `false` where threads
+/// cannot be pinned or the OS refused.
+#[cfg(target_os = "linux")]
+pub fn pin_current(cpus: &[usize]) -> bool {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+
+        for &cpu in cpus.iter().filter(|&&cpu| cpu < libc::CPU_SETSIZE as usize) {
+            libc::CPU_SET(cpu, &mut set);
+        }
+
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current(_: &[usize]) -> bool {
+    false
+}
+
+/// The CPU the calling thread is running on, or `None` where that is
+/// unknown.
+#[cfg(target_os = "linux")]
+pub fn current_cpu() -> Option<usize> {
+    match unsafe { libc::sched_getcpu() } {
+        -1 => None,
+        cpu => Some(cpu as usize),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn current_cpu() -> Option<usize> {
+    None
+}
+
+/// The CPUs the calling thread may run on, or `None` where that is unknown.
+#[cfg(target_os = "linux")]
+pub fn current() -> Option<Vec<usize>> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return None;
+        }
+
+        Some(
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn current() -> Option<Vec<usize>> {
+    None
+}
+
+fn read_cpus(device: &str) -> Option<Vec<usize>> {
+    parse_list(&fs::read_to_string(format!("/sys/devices/{}/cpus", device)).ok()?)
+}
+
+// Parses the kernel's CPU list format, e.g. `0-3,8,10-11`.
+pub(crate) fn parse_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        match range.split_once('-') {
+            Some((first, last)) => cpus.extend(first.parse::<usize>().ok()?..=last.parse().ok()?),
+            None => cpus.push(range.parse().ok()?),
+        }
+    }
+
+    Some(cpus)
+}
+
+fn by_capacity() -> Option<(Vec<usize>, Vec<usize>)> {
+    let mut capacities = Vec::new();
+
+    for cpu in 0.. {
+        let path = format!("/sys/devices/system/cpu/cpu{}/cpu_capacity", cpu);
+
+        match fs::read_to_string(path) {
+            Ok(capacity) => capacities.push((cpu, capacity.trim().parse::<u64>().ok()?)),
+            Err(_) => break,
+        }
+    }
+
+    let max = capacities.iter().map(|&(_, capacity)| capacity).max()?;
+    let (big, little): (Vec<_>, Vec<_>) = capacities
+        .into_iter()
+        .partition(|&(_, capacity)| capacity == max);
+
+    if little.is_empty() {
+        return None;
+    }
+
+    Some((
+        big.into_iter().map(|(cpu, _)| cpu).collect(),
+        little.into_iter().map(|(cpu, _)| cpu).collect(),
+    ))
+}
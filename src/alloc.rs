@@ -0,0 +1,98 @@
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::job::{Job, JobBox};
+
+/// Accounts for the job boxes the pool allocates on behalf of its users, see
+/// `TPBuilder::allocator`.
+///
+/// `reserve` is called before an allocation and may refuse it; every
+/// successful reservation is matched by a `release` once the memory is freed.
+pub trait Allocator: Send + Sync {
+    fn reserve(&self, bytes: usize) -> bool;
+    fn release(&self, bytes: usize);
+}
+
+/// Allocator that refuses reservations past a fixed number of bytes.
+#[derive(Debug)]
+pub struct Budget {
+    limit: usize,
+    used: AtomicUsize,
+}
+
+impl Budget {
+    pub fn new(limit: usize) -> Budget {
+        Budget {
+            limit,
+            used: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::SeqCst)
+    }
+}
+
+impl Allocator for Budget {
+    fn reserve(&self, bytes: usize) -> bool {
+        let mut used = self.used.load(Ordering::SeqCst);
+
+        loop {
+            if used + bytes > self.limit {
+                return false;
+            }
+
+            match self
+                .used
+                .compare_exchange(used, used + bytes, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => return true,
+                Err(actual) => used = actual,
+            }
+        }
+    }
+
+    fn release(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::SeqCst);
+    }
+}
+
+struct Accounted<F> {
+    job: Option<F>,
+    allocator: Arc<dyn Allocator>,
+}
+
+impl<F: FnOnce() + Send + 'static> Job for Accounted<F> {
+    fn call(mut self) {
+        if let Some(job) = self.job.take() {
+            job();
+        }
+    }
+}
+
+impl<F> Drop for Accounted<F> {
+    fn drop(&mut self) {
+        self.allocator.release(mem::size_of::<Self>());
+    }
+}
+
+/// Boxes `job`, charging the box to `allocator` when one is set.
+pub fn boxed<F>(allocator: Option<&Arc<dyn Allocator>>, job: F) -> Result<Box<dyn JobBox>, F>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let allocator = match allocator {
+        Some(allocator) => allocator,
+        None => return Ok(Box::new(job)),
+    };
+
+    if !allocator.reserve(mem::size_of::<Accounted<F>>()) {
+        return Err(job);
+    }
+
+    Ok(Box::new(Accounted {
+        job: Some(job),
+        allocator: allocator.clone(),
+    }))
+}
@@ -0,0 +1,75 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+thread_local! {
+    static BYTES: Cell<u64> = const { Cell::new(0) };
+    static COUNT: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Global allocator shim that counts allocations per thread, so workers can
+/// attribute them to the job they are running. Install it with
+/// `#[global_allocator]`; without it the recorded numbers stay at zero.
+#[derive(Debug, Default)]
+pub struct CountingAlloc<A = System> {
+    inner: A,
+}
+
+impl<A> CountingAlloc<A> {
+    pub const fn new(inner: A) -> CountingAlloc<A> {
+        CountingAlloc { inner }
+    }
+}
+
+/// Bytes and number of allocations made by a job.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct AllocStats {
+    pub bytes: u64,
+    pub count: u64,
+}
+
+impl AllocStats {
+    pub fn since(self, start: AllocStats) -> AllocStats {
+        AllocStats {
+            bytes: self.bytes.wrapping_sub(start.bytes),
+            count: self.count.wrapping_sub(start.count),
+        }
+    }
+}
+
+/// Allocations made on the current thread so far.
+pub fn current() -> AllocStats {
+    AllocStats {
+        bytes: BYTES.try_with(Cell::get).unwrap_or(0),
+        count: COUNT.try_with(Cell::get).unwrap_or(0),
+    }
+}
+
+fn record(bytes: usize) {
+    let _ = BYTES.try_with(|b| b.set(b.get().wrapping_add(bytes as u64)));
+    let _ = COUNT.try_with(|c| c.set(c.get().wrapping_add(1)));
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAlloc<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size);
+        self.inner.realloc(ptr, layout, new_size)
+    }
+}
@@ -0,0 +1,52 @@
+use bumpalo::Bump;
+use std::cell::RefCell;
+use std::ops::Deref;
+
+thread_local! {
+    static ARENA: RefCell<Option<Bump>> = const { RefCell::new(None) };
+}
+
+/// Borrow of the current worker's bump arena, returned to the worker on drop.
+///
+/// The arena is reset after every job, so allocations never outlive the job
+/// that made them.
+pub struct Arena {
+    bump: Option<Bump>,
+}
+
+/// Returns the arena of the current worker if the pool was built with
+/// `TPBuilder::arena` and the arena is not already borrowed.
+pub fn arena() -> Option<Arena> {
+    ARENA.with(|slot| {
+        slot.borrow_mut()
+            .take()
+            .map(|bump| Arena { bump: Some(bump) })
+    })
+}
+
+pub fn install(capacity: usize) {
+    ARENA.with(|slot| *slot.borrow_mut() = Some(Bump::with_capacity(capacity)));
+}
+
+pub fn reset() {
+    ARENA.with(|slot| {
+        if let Some(bump) = slot.borrow_mut().as_mut() {
+            bump.reset();
+        }
+    });
+}
+
+impl Deref for Arena {
+    type Target = Bump;
+
+    fn deref(&self) -> &Bump {
+        self.bump.as_ref().unwrap()
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        let bump = self.bump.take();
+        ARENA.with(|slot| *slot.borrow_mut() = bump);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{
+    core::ThreadPool,
+    error::JobError,
+    handle::{self, JobHandle},
+    job::JobBox,
+};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Runs `f` on `pool`, like `async_std::task::spawn_blocking` runs it on
+/// async-std's blocking threads, which take it instead if `pool` refuses it.
+pub fn spawn_blocking<F, R>(pool: &ThreadPool<Box<dyn JobBox>>, f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (job, handle) = handle::wrap(f);
+
+    if let Err(err) = pool.send_fn(job) {
+        let job = err.into_inner();
+
+        // The handle reports a panic; the blocking thread must survive it.
+        async_std::task::spawn_blocking(move || {
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| job.call_box()));
+        });
+    }
+
+    JoinHandle(handle)
+}
+
+/// Resolves to the output of the closure given to `spawn_blocking` and, as
+/// with `async_std::task::JoinHandle`, resumes its panic in the awaiting
+/// task. Panics as well if `close_force` dropped the closure unrun.
+pub struct JoinHandle<R>(JobHandle<R>);
+
+impl<R> Future for JoinHandle<R> {
+    type Output = R;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<R> {
+        match Pin::new(&mut self.0).poll(cx) {
+            Poll::Ready(Ok(value)) => Poll::Ready(value),
+            Poll::Ready(Err(JobError::Panicked(message))) => {
+                panic::resume_unwind(Box::new(message))
+            }
+            Poll::Ready(Err(JobError::Cancelled)) => panic!("blocking job cancelled by the pool"),
+            Poll::Ready(Err(JobError::Taken)) => panic!("JoinHandle polled after completion"),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -2,10 +2,59 @@ use crate::{
     lifecycle::{Lifecycle, LIFECYCLE_BITS},
     state::State,
 };
-use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(not(feature = "mutex-state"))]
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+#[cfg(feature = "mutex-state")]
+use std::sync::Mutex;
 
 pub struct AtomicState {
-    atomic: AtomicUsize,
+    atomic: Cell,
+}
+
+#[cfg(not(feature = "mutex-state"))]
+type Cell = AtomicUsize;
+
+// Same interface as the `AtomicUsize` it replaces, with every operation done
+// under a lock, so tools like TSan and Miri see plain lock-guarded state
+// instead of the packed atomic. Orderings are implied by the lock.
+#[cfg(feature = "mutex-state")]
+struct Cell(Mutex<usize>);
+
+#[cfg(feature = "mutex-state")]
+impl Cell {
+    fn new(val: usize) -> Cell {
+        Cell(Mutex::new(val))
+    }
+
+    fn load(&self, _: Ordering) -> usize {
+        *self.0.lock().unwrap()
+    }
+
+    fn compare_exchange(
+        &self,
+        current: usize,
+        new: usize,
+        _: Ordering,
+        _: Ordering,
+    ) -> Result<usize, usize> {
+        let mut val = self.0.lock().unwrap();
+
+        if *val == current {
+            *val = new;
+            Ok(current)
+        } else {
+            Err(*val)
+        }
+    }
+
+    fn fetch_sub(&self, sub: usize, _: Ordering) -> usize {
+        let mut val = self.0.lock().unwrap();
+        let prev = *val;
+
+        *val -= sub;
+        prev
+    }
 }
 
 pub const CAPACITY: usize = (1 << (32 - 3)) - 1;
@@ -15,7 +64,7 @@ impl AtomicState {
         let state = State::of(lifecycle).as_usize();
 
         AtomicState {
-            atomic: AtomicUsize::new(state),
+            atomic: Cell::new(state),
         }
     }
 
@@ -26,9 +75,14 @@ impl AtomicState {
     }
 
     fn compare_and_swap(&self, expect: State, val: State) -> State {
-        let actual =
-            self.atomic
-                .compare_and_swap(expect.as_usize(), val.as_usize(), Ordering::SeqCst);
+        let actual = match self.atomic.compare_exchange(
+            expect.as_usize(),
+            val.as_usize(),
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            Ok(prev) | Err(prev) => prev,
+        };
 
         State::load(actual)
     }
@@ -36,14 +90,15 @@ impl AtomicState {
     pub fn compare_and_inc_worker_count(&self, expect: State) -> Result<State, State> {
         let expect_usize = expect.as_usize();
         let next_usize = expect_usize + (1 << LIFECYCLE_BITS);
-        let actual_usize = self
-            .atomic
-            .compare_and_swap(expect_usize, next_usize, Ordering::SeqCst);
 
-        if actual_usize == expect_usize {
-            Ok(expect)
-        } else {
-            Err(State::load(actual_usize))
+        match self.atomic.compare_exchange(
+            expect_usize,
+            next_usize,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            Ok(_) => Ok(expect),
+            Err(actual_usize) => Err(State::load(actual_usize)),
         }
     }
 
@@ -54,8 +109,13 @@ impl AtomicState {
 
         let num = expect.as_usize();
         self.atomic
-            .compare_and_swap(num, num - (1 << LIFECYCLE_BITS), Ordering::SeqCst)
-            == num
+            .compare_exchange(
+                num,
+                num - (1 << LIFECYCLE_BITS),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            )
+            .is_ok()
     }
 
     pub fn fetch_dec_worker_count(&self) -> State {
@@ -82,6 +142,26 @@ impl AtomicState {
         }
     }
 
+    /// Forgets every worker, e.g. in a forked child where their threads are
+    /// gone.
+    pub fn reset_worker_count(&self) {
+        let mut state = self.load();
+
+        loop {
+            let actual = self.compare_and_swap(state, State::of(state.lifecycle()));
+
+            if state == actual {
+                return;
+            }
+
+            state = actual;
+        }
+    }
+
+    pub fn try_transition_to_shutdown(&self) -> bool {
+        self.try_transition_to_lifecycle(Lifecycle::Shutdown)
+    }
+
     pub fn try_transition_to_stop(&self) -> bool {
         self.try_transition_to_lifecycle(Lifecycle::Stop)
     }
@@ -0,0 +1,43 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::Arc;
+
+/// Opaque value attached to a job at submission, e.g. a request or trace id.
+///
+/// While the job runs, the context is available from any code on the worker
+/// thread through `JobContext::current`.
+#[derive(Clone)]
+pub struct JobContext {
+    value: Arc<dyn Any + Send + Sync>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<JobContext>> = const { RefCell::new(None) };
+}
+
+impl JobContext {
+    pub fn new<V: Any + Send + Sync>(value: V) -> JobContext {
+        JobContext {
+            value: Arc::new(value),
+        }
+    }
+
+    pub fn get<V: Any>(&self) -> Option<&V> {
+        self.value.downcast_ref()
+    }
+
+    pub fn current() -> Option<JobContext> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    pub fn enter(context: Option<JobContext>) -> Option<JobContext> {
+        CURRENT.with(|current| current.replace(context))
+    }
+}
+
+impl fmt::Debug for JobContext {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("JobContext").finish()
+    }
+}
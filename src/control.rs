@@ -0,0 +1,34 @@
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::sync::Mutex;
+
+/// Wakes every idle worker when the pool's lifecycle or size changes.
+///
+/// Workers subscribe before they look at the pool state and then block on
+/// the subscription next to the queue. `notify_all` disconnects every
+/// subscription handed out so far, so a change made after a worker looked at
+/// the state always interrupts its wait.
+pub struct Control {
+    current: Mutex<(Sender<()>, Receiver<()>)>,
+}
+
+impl Control {
+    pub fn new() -> Control {
+        Control {
+            current: Mutex::new(bounded(0)),
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<()> {
+        self.current.lock().unwrap().1.clone()
+    }
+
+    pub fn notify_all(&self) {
+        *self.current.lock().unwrap() = bounded(0);
+    }
+}
+
+impl Default for Control {
+    fn default() -> Control {
+        Control::new()
+    }
+}
@@ -1,308 +1,2553 @@
-use std::sync::{Arc, Condvar, Mutex};
-use std::time::Duration;
-use std::{fmt, usize};
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+#[cfg(feature = "persistence")]
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock, Weak};
+use std::task::Wake;
+use std::time::{Duration, Instant};
+use std::{fmt, iter, mem};
 
-use crate::{atomic, job, lifecycle, worker};
+#[cfg(feature = "futures")]
+use crate::executor;
+#[cfg(feature = "fault-injection")]
+use crate::fault::{FaultPolicy, Injector};
+#[cfg(feature = "topology")]
+use crate::topology::Numa;
+use crate::{
+    affinity, alloc, atomic, context, control, error, handle, idle, job, lifecycle, outcome,
+    priority, progress, registry, rng, rt, scope, scratch, sender, slots, stats, task, timer,
+    worker,
+};
+use affinity::{CoreKind, Pinning};
+use alloc::Allocator;
 use atomic::{AtomicState, CAPACITY};
+use context::JobContext;
+use control::Control;
 use crossbeam_channel::{
-    bounded, Receiver as CCReceiver, SendError, SendTimeoutError, Sender as CCSender, TryRecvError,
-    TrySendError,
+    bounded, unbounded, Receiver as CCReceiver, Select, SendError, Sender as CCSender,
+    TryRecvError, TrySendError,
 };
-use job::{Job, JobBox};
+use error::{ConfigError, GroupTooLarge, SubmitError, MIN_STACK_SIZE, STACK_GRANULARITY};
+use handle::JobHandle;
+use idle::IdleStrategy;
+use job::{Executor, Job, JobBox, JobGuard, JobLayer};
 use lifecycle::Lifecycle;
 use num_cpus;
-use worker::Worker;
+use outcome::{DeadlineMiss, JobMeta, JobOutcome, JobTimestamps};
+use priority::{Lanes, Priority};
+use progress::{Fractions, ProgressReceiver, ProgressSink, ProgressSummary};
+use registry::{Breadcrumb, JobId, JobStatus, Registry};
+use rt::RtProducer;
+use scope::{PanicPolicy, Region, RegionResult, Scope};
+use sender::{Lease, PoolSender};
+#[cfg(feature = "persistence")]
+use serde::{de::DeserializeOwned, Serialize};
+use slots::{Slot, Slots};
+use stats::{KindStats, PoolDump, PoolStats, WorkerCounters, WorkerStats};
+use task::Task;
+use timer::{ScheduleHandle, Sleep, Timeout, Timer, TimerLock};
+use worker::{Worker, WorkerContext};
 
 pub struct ThreadPool<T> {
     inner: Arc<Inner>,
-    pub tx: CCSender<T>,
-    rx: CCReceiver<T>,
+    tx: CCSender<Task<T>>,
+    rx: CCReceiver<Task<T>>,
+    lanes: Arc<Lanes<T>>,
+    groups: Arc<RwLock<HashMap<String, Group<T>>>>,
+}
+
+struct Group<T> {
+    tx: CCSender<Task<T>>,
+    rx: CCReceiver<Task<T>>,
+    lanes: Arc<Lanes<T>>,
 }
 
-#[derive(Debug)]
+#[derive(Clone)]
 pub struct TPBuilder {
     instance: Config,
+    restored: Arc<Mutex<Option<Box<dyn Any + Send>>>>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum QueueCapacity {
+    Bounded(usize),
+    Unbounded,
+}
+
+/// Stack size presets for `TPBuilder::stack_preset`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum StackSize {
+    /// 64 KiB, for many workers running shallow jobs.
+    Small,
+    /// The standard library's default, 2 MiB unless `RUST_MIN_STACK` says
+    /// otherwise.
+    #[default]
+    Default,
+    /// 64 MiB, for deeply recursive jobs such as parsers or tree walks.
+    DeepRecursion,
+}
+
+impl StackSize {
+    /// Size in bytes, `None` for the standard library's default.
+    pub fn bytes(self) -> Option<usize> {
+        match self {
+            StackSize::Small => Some(64 * 1024),
+            StackSize::Default => None,
+            StackSize::DeepRecursion => Some(64 * 1024 * 1024),
+        }
+    }
+}
+
+/// What `send` does with a job when the queue is full and no more workers
+/// can be started.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum RejectionPolicy {
+    /// Fails with the job handed back.
+    Abort,
+    /// Waits for room in the queue.
+    #[default]
+    Block,
+    /// Runs the job on the calling thread.
+    CallerRuns,
+    /// Drops the job.
+    Discard,
+    /// Drops the oldest queued job to make room. Needs a queue that holds
+    /// jobs, so not `queue_capacity(0)`.
+    DiscardOldest,
+}
+
+/// When the pool starts its core workers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum SpawnPolicy {
+    /// Starts every core worker when the pool is built.
+    Eager,
+    /// Starts a worker per job sent until the core size is reached.
+    #[default]
+    OnDemand,
+    /// Only starts workers through `prestart_core_thread(s)`; sends never
+    /// do, so a full queue falls back to the rejection policy, which cannot
+    /// be `Block` unless the queue is unbounded. `close` still starts one
+    /// worker if jobs are queued and none is left to run them.
+    Manual,
+}
+
+/// How queued jobs are shared between workers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Scheduler {
+    /// Every worker takes from the pool's queue.
+    #[default]
+    Shared,
+    /// Each worker also has a local queue for the jobs it sends, which it
+    /// takes from first and idle workers steal from. Jobs sent from other
+    /// threads go to the pool's queue. Local queues are unbounded, so
+    /// `queue_capacity` only limits the pool's queue.
+    WorkStealing,
 }
 
+#[derive(Clone)]
 pub struct Config {
+    pub name: Option<String>,
+    /// Worker threads are named `<prefix>-<index>`. Defaults to `name`.
+    pub name_prefix: Option<String>,
+    /// Core size: workers started eagerly, which never retire unless
+    /// `max_size` is left unset and a `timeout` is configured.
     pub size: usize,
+    /// Upper bound for extra workers started when the queue is full. They
+    /// retire after `timeout` once idle.
+    pub max_size: Option<usize>,
+    pub queue_capacity: Option<QueueCapacity>,
+    pub rejection: RejectionPolicy,
+    pub spawn: SpawnPolicy,
+    pub scheduler: Scheduler,
+    /// Jobs sent from the pool's own workers are queued as `Priority::High`.
+    pub boost_continuations: bool,
+    /// Measure the CPU time of every job.
+    pub cpu_time: bool,
+    /// Leave a `Breadcrumb` for every queued job.
+    pub breadcrumbs: bool,
     pub timeout: Option<Duration>,
     pub stack_size: Option<usize>,
-    pub mount: Option<Arc<Fn() + Send + Sync>>,
-    pub unmount: Option<Arc<Fn() + Send + Sync>>,
+    pub cores: CoreKind,
+    pub pinning: Pinning,
+    #[cfg(feature = "topology")]
+    pub numa: bool,
+    pub mount: Option<WorkerHook>,
+    pub unmount: Option<WorkerHook>,
+    pub scratch: Option<ScratchInit>,
+    pub seed_rng: Option<RngSeed>,
+    pub allocator: Option<Arc<dyn Allocator>>,
+    pub shed: Option<Shed>,
+    pub guard: Option<Arc<dyn JobGuard>>,
+    /// Outermost first.
+    pub layers: Vec<Arc<dyn JobLayer>>,
+    pub slots: Option<(usize, usize)>,
+    pub idle_tick: Option<(Duration, TickHook)>,
+    pub handler: Option<TickHook>,
+    pub on_disconnect: Option<Hook>,
+    pub panic_handler: Option<PanicHandler>,
+    pub on_deadline_miss: Option<MissHandler>,
+    pub timestamps: Option<TimestampSink>,
+    pub before_job: Option<BeforeJob>,
+    pub after_job: Option<AfterJob>,
+    pub idle: Option<Arc<dyn IdleStrategy>>,
+    #[cfg(feature = "arena")]
+    pub arena: Option<usize>,
+    #[cfg(feature = "fault-injection")]
+    pub faults: Option<FaultPolicy>,
+    pub workers: bool,
+}
+
+pub type Hook = Arc<dyn Fn() + Send + Sync>;
+
+pub type WorkerHook = Arc<dyn Fn(&WorkerContext) + Send + Sync>;
+
+pub type ScratchInit = Arc<dyn Fn(&WorkerContext) -> Box<dyn Any> + Send + Sync>;
+
+pub type RngSeed = Arc<dyn Fn(usize) -> u64 + Send + Sync>;
+
+pub type Shed = Arc<dyn Fn(&PoolStats) -> f64 + Send + Sync>;
+
+pub type TickHook = Arc<dyn Fn(&WorkerContext) + Send + Sync>;
+
+pub type PanicHandler = Arc<dyn Fn(Box<dyn Any + Send>) + Send + Sync>;
+
+pub type MissHandler = Arc<dyn Fn(&DeadlineMiss) + Send + Sync>;
+
+pub type TimestampSink = Arc<dyn Fn(&JobTimestamps) + Send + Sync>;
+
+pub type BeforeJob = Arc<dyn Fn(&JobMeta) + Send + Sync>;
+
+pub type AfterJob = Arc<dyn Fn(&JobMeta, Duration) + Send + Sync>;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HookKind {
+    Mount,
+    Unmount,
+}
+
+#[derive(Default)]
+pub struct Hooks {
+    next_id: u64,
+    mount: Vec<(u64, WorkerHook)>,
+    unmount: Vec<(u64, WorkerHook)>,
+}
+
+#[must_use = "the job is not submitted until `commit` is called"]
+pub struct Prepared<'a, T> {
+    pool: &'a ThreadPool<T>,
+    task: Task<T>,
+}
+
+#[must_use = "the hook is removed when the handle is dropped"]
+pub struct HookHandle {
+    inner: Weak<Inner>,
+    kind: HookKind,
+    id: u64,
 }
 
 pub struct Inner {
     pub state: AtomicState,
+    pub active: AtomicUsize,
+    pub completed: AtomicU64,
+    pub panicked: AtomicU64,
+    pub submitted: AtomicU64,
+    pub deadline_misses: AtomicU64,
+    pub progress: Fractions,
+    pub size: AtomicUsize,
+    pub next_id: AtomicU64,
+    pub next_worker: AtomicUsize,
+    pub shed_seq: AtomicU64,
+    /// Workers that died outside a job since one last finished a job.
+    pub crashes: AtomicUsize,
+    pub kinds: Mutex<HashMap<&'static str, KindStats>>,
+    pub workers: Mutex<Vec<Arc<WorkerCounters>>>,
+    pub jobs: Arc<Registry>,
+    pub slots: Option<Slots>,
+    pub reserved: AtomicUsize,
+    pub outcomes: (CCSender<JobOutcome>, CCReceiver<JobOutcome>),
+    pub hooks: RwLock<Hooks>,
     pub termination_mutex: Mutex<()>,
     pub termination_signal: Condvar,
+    pub shutdown_tx: Mutex<Option<CCSender<()>>>,
+    pub shutdown_rx: CCReceiver<()>,
+    pub control: Control,
+    pub timer: Timer,
     pub config: Config,
+    /// CPUs workers are placed on, from `Config::cores` and
+    /// `Config::pinning`.
+    pub cpus: Option<Vec<usize>>,
+    #[cfg(feature = "topology")]
+    pub(crate) numa: Option<Numa>,
+    // Number of local queues in the pool's `Lanes`.
+    locals: usize,
+    #[cfg(feature = "fault-injection")]
+    pub faults: Option<Injector>,
+    children: Mutex<Vec<Arc<dyn Child>>>,
+    handles: AtomicUsize,
+    /// Counts the workers running a job, see `ThreadPool::prepare_fork`.
+    pub fork: Fork,
+}
+
+// A pool adopted by another one, erased over its job type. It does not count
+// as a handle, so dropping every `ThreadPool` of the child still closes it.
+trait Child: Send + Sync {
+    fn close(&self);
+    fn close_force(&self);
+    fn await_termination_until(&self, deadline: Option<Instant>) -> bool;
+}
+
+struct ChildRef<T> {
+    inner: Arc<Inner>,
+    rx: CCReceiver<Task<T>>,
+    lanes: Arc<Lanes<T>>,
+}
+
+impl<T: Job> Child for ChildRef<T> {
+    fn close(&self) {
+        self.inner.close()
+    }
+
+    fn close_force(&self) {
+        for task in self.inner.close_force(|| self.lanes.drain(&self.rx)) {
+            if task.critical {
+                run_here(task);
+            }
+        }
+    }
+
+    fn await_termination_until(&self, deadline: Option<Instant>) -> bool {
+        self.inner.await_termination_until(deadline)
+    }
+}
+
+// A job waiting in the pool's timer, see `ThreadPool::send_at`.
+struct Delayed<T> {
+    pool: ThreadPool<T>,
+    task: Mutex<Option<Task<T>>>,
+}
+
+impl<T: Job> Wake for Delayed<T> {
+    fn wake(self: Arc<Self>) {
+        if let Some(mut task) = self.task.lock().unwrap().take() {
+            task.queued_at = Instant::now();
+            // Dropped if the pool was closed in the meantime.
+            let _ = self.pool.push(task);
+        }
+    }
+}
+
+// A recurrence in the pool's timer, see `ThreadPool::send_repeating`. It
+// re-registers itself after queueing each job.
+struct Repeating<T, F> {
+    pool: ThreadPool<T>,
+    factory: F,
+    interval: Duration,
+    next: Mutex<Instant>,
+    handle: ScheduleHandle,
+}
+
+impl<T, F> Wake for Repeating<T, F>
+where
+    T: Job,
+    F: Fn() -> T + Send + Sync + 'static,
+{
+    fn wake(self: Arc<Self>) {
+        if self.handle.is_cancelled() || self.pool.inner.state.load().is_shutdown() {
+            return;
+        }
+
+        let _ = self.pool.push(self.pool.inner.task((self.factory)()));
+
+        let next = {
+            let now = Instant::now();
+            let mut next = self.next.lock().unwrap();
+
+            *next += self.interval;
+            // Ticks missed while the timer was held up are skipped, not
+            // queued back to back.
+            if *next <= now {
+                *next = now + self.interval;
+            }
+            *next
+        };
+        let timer = &self.pool.inner.timer;
+
+        timer.wake_at(next, self.clone().into());
+    }
+}
+
+/// How `ThreadPool::shutdown` ended.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ShutdownResult {
+    /// Every queued job ran before the deadline.
+    Drained,
+    /// The deadline passed: `abandoned` queued jobs were dropped, `critical`
+    /// queued jobs were run on the calling thread and `running` jobs were
+    /// left to finish on their own.
+    Forced {
+        abandoned: usize,
+        critical: usize,
+        running: usize,
+    },
+}
+
+/// Keeps the workers from starting jobs while the process forks; see
+/// `ThreadPool::prepare_fork`. Dropping it lets them go on.
+#[must_use]
+pub struct ForkGuard<'a> {
+    fork: &'a Fork,
+    timer: Option<TimerLock<'a>>,
+}
+
+impl Drop for ForkGuard<'_> {
+    fn drop(&mut self) {
+        self.timer.take();
+        self.fork.forking.store(false, Ordering::SeqCst);
+        self.fork.notify();
+    }
+}
+
+// Workers count themselves in `running` while they run a job and hold off
+// while `forking` is set. A worker blocked on other jobs, e.g. in a scope,
+// steps out of the count until it goes on, so a fork does not wait for jobs
+// it holds back. The mutex is only taken to sleep and wake, never across the
+// fork itself, as a forked child could not take it again.
+#[derive(Default)]
+pub struct Fork {
+    running: AtomicUsize,
+    forking: AtomicBool,
+    lock: Mutex<()>,
+    changed: Condvar,
+}
+
+impl Fork {
+    /// Counts one more running job, once no fork is in progress.
+    pub fn enter(&self) {
+        loop {
+            self.running.fetch_add(1, Ordering::SeqCst);
+
+            if !self.forking.load(Ordering::SeqCst) {
+                return;
+            }
+
+            self.leave();
+
+            let mut lock = self.lock.lock().unwrap();
+            while self.forking.load(Ordering::SeqCst) {
+                lock = self.changed.wait(lock).unwrap();
+            }
+        }
+    }
+
+    pub fn leave(&self) {
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        if self.forking.load(Ordering::SeqCst) {
+            self.notify();
+        }
+    }
+
+    fn block(&self) {
+        let mut lock = self.lock.lock().unwrap();
+
+        while self
+            .forking
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            lock = self.changed.wait(lock).unwrap();
+        }
+
+        while self.running.load(Ordering::SeqCst) > 0 {
+            lock = self.changed.wait(lock).unwrap();
+        }
+    }
+
+    fn notify(&self) {
+        let _lock = self.lock.lock().unwrap();
+        self.changed.notify_all();
+    }
 }
 
 impl fmt::Debug for Config {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        const SOME: &'static &'static str = &"Some(_)";
-        const NONE: &'static &'static str = &"None";
+        const SOME: &&str = &"Some(_)";
+        const NONE: &&str = &"None";
 
-        fmt.debug_struct("ThreadPool")
+        let mut fmt = fmt.debug_struct("ThreadPool");
+
+        fmt.field("name", &self.name)
+            .field("name_prefix", &self.name_prefix)
             .field("size", &self.size)
+            .field("max_size", &self.max_size)
+            .field("queue_capacity", &self.queue_capacity)
+            .field("rejection", &self.rejection)
+            .field("spawn", &self.spawn)
+            .field("scheduler", &self.scheduler)
+            .field("boost_continuations", &self.boost_continuations)
+            .field("cpu_time", &self.cpu_time)
+            .field("breadcrumbs", &self.breadcrumbs)
             .field("timeout", &self.timeout)
             .field("stack_size", &self.stack_size)
+            .field("cores", &self.cores)
+            .field("pinning", &self.pinning)
             .field("mount", if self.mount.is_some() { SOME } else { NONE })
             .field("unmount", if self.unmount.is_some() { SOME } else { NONE })
+            .field("scratch", if self.scratch.is_some() { SOME } else { NONE })
+            .field(
+                "seed_rng",
+                if self.seed_rng.is_some() { SOME } else { NONE },
+            )
+            .field("workers", &self.workers);
+
+        #[cfg(feature = "arena")]
+        fmt.field("arena", &self.arena);
+        #[cfg(feature = "fault-injection")]
+        fmt.field("faults", &self.faults);
+        #[cfg(feature = "topology")]
+        fmt.field("numa", &self.numa);
+
+        fmt.finish()
+    }
+}
+
+impl fmt::Debug for TPBuilder {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        const SOME: &&str = &"Some(_)";
+        const NONE: &&str = &"None";
+
+        fmt.debug_struct("TPBuilder")
+            .field("instance", &self.instance)
+            .field(
+                "restored",
+                if self.restored.lock().unwrap().is_some() {
+                    SOME
+                } else {
+                    NONE
+                },
+            )
             .finish()
     }
-}
+}
+
+impl Default for TPBuilder {
+    fn default() -> TPBuilder {
+        TPBuilder::new()
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        let num_cpus = num_cpus::get();
+
+        Config {
+            name: None,
+            name_prefix: None,
+            size: num_cpus,
+            max_size: None,
+            queue_capacity: None,
+            rejection: RejectionPolicy::Block,
+            spawn: SpawnPolicy::OnDemand,
+            scheduler: Scheduler::Shared,
+            boost_continuations: false,
+            cpu_time: false,
+            breadcrumbs: false,
+            timeout: None,
+            stack_size: None,
+            cores: CoreKind::Any,
+            pinning: Pinning::Unpinned,
+            #[cfg(feature = "topology")]
+            numa: false,
+            mount: None,
+            unmount: None,
+            scratch: None,
+            seed_rng: None,
+            allocator: None,
+            shed: None,
+            guard: None,
+            layers: Vec::new(),
+            slots: None,
+            idle_tick: None,
+            handler: None,
+            on_disconnect: None,
+            panic_handler: None,
+            on_deadline_miss: None,
+            timestamps: None,
+            before_job: None,
+            after_job: None,
+            idle: None,
+            #[cfg(feature = "arena")]
+            arena: None,
+            #[cfg(feature = "fault-injection")]
+            faults: None,
+            workers: true,
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.size == 0 {
+            return Err(ConfigError::ZeroSize);
+        }
+
+        if let Some(max) = self.max_size {
+            if max < self.size {
+                return Err(ConfigError::MaxBelowCore {
+                    core: self.size,
+                    max,
+                });
+            }
+        }
+
+        match self.stack_size {
+            Some(requested) if requested < MIN_STACK_SIZE => Err(ConfigError::StackTooSmall {
+                requested,
+                minimum: MIN_STACK_SIZE,
+            }),
+            Some(requested) if round_stack(requested).is_none() => {
+                Err(ConfigError::StackTooLarge { requested })
+            }
+            _ if self.pinning == Pinning::Cpus(Vec::new()) => Err(ConfigError::NoCpus),
+            _ => self.validate_policies(),
+        }
+    }
+
+    fn validate_policies(&self) -> Result<(), ConfigError> {
+        let bounded = match self.queue_capacity {
+            Some(QueueCapacity::Bounded(capacity)) => Some(capacity),
+            Some(QueueCapacity::Unbounded) => None,
+            None => Some(self.size),
+        };
+
+        // Nothing frees room in the queue until a worker is started by hand,
+        // so a send to a full queue would block forever.
+        if self.spawn == SpawnPolicy::Manual
+            && self.rejection == RejectionPolicy::Block
+            && bounded.is_some()
+        {
+            return Err(ConfigError::ConflictingPolicies(
+                "SpawnPolicy::Manual",
+                "RejectionPolicy::Block with a bounded queue",
+            ));
+        }
+
+        if self.rejection == RejectionPolicy::DiscardOldest && bounded == Some(0) {
+            return Err(ConfigError::ConflictingPolicies(
+                "queue_capacity(0)",
+                "RejectionPolicy::DiscardOldest",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The stack size workers are spawned with: `stack_size` rounded up to
+    /// a multiple of `STACK_GRANULARITY`.
+    pub fn thread_stack_size(&self) -> Option<usize> {
+        self.stack_size.and_then(round_stack)
+    }
+}
+
+// Also rejects sizes no address space could hold.
+fn round_stack(size: usize) -> Option<usize> {
+    let rounded = size.checked_next_multiple_of(STACK_GRANULARITY)?;
+
+    if rounded > isize::MAX as usize {
+        None
+    } else {
+        Some(rounded)
+    }
+}
+
+impl From<Config> for TPBuilder {
+    fn from(config: Config) -> TPBuilder {
+        TPBuilder {
+            instance: config,
+            restored: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl TPBuilder {
+    pub fn new() -> TPBuilder {
+        TPBuilder::from(Config::default())
+    }
+
+    pub fn single() -> TPBuilder {
+        TPBuilder::fixed(1)
+    }
+
+    pub fn fixed(size: usize) -> TPBuilder {
+        TPBuilder::new().size(size).unbounded()
+    }
+
+    /// Starts a worker for every job that finds none idle and retires
+    /// workers after a minute without work. The queue is a hand-off that
+    /// holds no jobs: a job sent once no more workers can start goes
+    /// to the rejection policy at once, so `Discard` drops it, and
+    /// `DiscardOldest` fails validation. Set `queue_capacity` to buffer jobs
+    /// instead.
+    pub fn cached() -> TPBuilder {
+        TPBuilder::new()
+            .size(CAPACITY)
+            .timeout(Duration::from_secs(60))
+            .queue_capacity(0)
+    }
+
+    pub fn name<S: Into<String>>(mut self, val: S) -> Self {
+        self.instance.name = Some(val.into());
+        self
+    }
+
+    pub fn name_prefix<S: Into<String>>(mut self, val: S) -> Self {
+        self.instance.name_prefix = Some(val.into());
+        self
+    }
+
+    pub fn size(mut self, val: usize) -> Self {
+        self.instance.size = val;
+        self
+    }
+
+    pub fn core_size(self, val: usize) -> Self {
+        self.size(val)
+    }
+
+    pub fn max_size(mut self, val: usize) -> Self {
+        self.instance.max_size = Some(val);
+        self
+    }
+
+    /// Number of jobs the queue buffers before `send` blocks. Defaults to the
+    /// pool size.
+    pub fn queue_capacity(mut self, val: usize) -> Self {
+        self.instance.queue_capacity = Some(QueueCapacity::Bounded(val));
+        self
+    }
+
+    /// Backs the pool with an unbounded queue, so `send` never blocks.
+    pub fn unbounded(mut self) -> Self {
+        self.instance.queue_capacity = Some(QueueCapacity::Unbounded);
+        self
+    }
+
+    pub fn rejection_policy(mut self, val: RejectionPolicy) -> Self {
+        self.instance.rejection = val;
+        self
+    }
+
+    pub fn spawn_policy(mut self, val: SpawnPolicy) -> Self {
+        self.instance.spawn = val;
+        self
+    }
+
+    /// Gives each worker a local queue, see `Scheduler::WorkStealing`, which
+    /// cuts contention on the pool's queue when jobs send many small jobs.
+    pub fn scheduler(mut self, val: Scheduler) -> Self {
+        self.instance.scheduler = val;
+        self
+    }
+
+    pub fn timeout(mut self, val: Duration) -> Self {
+        self.instance.timeout = Some(val);
+        self
+    }
+
+    /// Stack size of every worker, rounded up to a multiple of
+    /// `STACK_GRANULARITY`. Building fails with a `ConfigError` if it is
+    /// below `MIN_STACK_SIZE`.
+    pub fn stack_size(mut self, val: usize) -> Self {
+        self.instance.stack_size = Some(val);
+        self
+    }
+
+    pub fn stack_preset(mut self, val: StackSize) -> Self {
+        self.instance.stack_size = val.bytes();
+        self
+    }
+
+    /// Keeps workers on one kind of core of a hybrid CPU. Ignored where the
+    /// CPU is not hybrid or threads cannot be pinned.
+    pub fn prefer_cores(mut self, val: CoreKind) -> Self {
+        self.instance.cores = val;
+        self
+    }
+
+    /// Pins each worker to a CPU of its own, taken from the `prefer_cores`
+    /// set if there is one and otherwise from the CPUs the building thread
+    /// may use; with the `topology` feature physical cores are handed out
+    /// before their SMT siblings. Ignored where threads cannot be pinned.
+    pub fn pin_workers(mut self, val: bool) -> Self {
+        self.instance.pinning = if val {
+            Pinning::PerCore
+        } else {
+            Pinning::Unpinned
+        };
+        self
+    }
+
+    /// Groups workers by NUMA node, spreading them evenly and keeping each
+    /// on its node's CPUs unless pinned otherwise. Every node gets its own
+    /// queue: `send` queues on the node of the calling thread, and idle
+    /// workers take jobs from their own node first, then from the main queue
+    /// and finally from the other nodes. Node queues are unbounded. Has no
+    /// effect where nodes cannot be detected.
+    #[cfg(feature = "topology")]
+    pub fn numa(mut self) -> Self {
+        self.instance.numa = true;
+        self
+    }
+
+    /// Pins worker `i` to `cpus[i % cpus.len()]`.
+    pub fn affinity(mut self, cpus: Vec<usize>) -> Self {
+        self.instance.pinning = Pinning::Cpus(cpus);
+        self
+    }
+
+    /// Called on every worker thread when it starts, e.g. to pin it to the
+    /// core matching its index.
+    pub fn mount<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&WorkerContext) + Send + Sync + 'static,
+    {
+        self.instance.mount = Some(Arc::new(f));
+        self
+    }
+
+    pub fn unmount<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&WorkerContext) + Send + Sync + 'static,
+    {
+        self.instance.unmount = Some(Arc::new(f));
+        self
+    }
+
+    /// `f` is called with the worker index when a worker starts; jobs on that
+    /// worker reach the value through `multix::scratch`.
+    pub fn scratch<F, S>(self, f: F) -> Self
+    where
+        F: Fn(usize) -> S + Send + Sync + 'static,
+        S: Any,
+    {
+        self.mount_with_state(move |worker| f(worker.index()))
+    }
+
+    /// Builds the state of each worker when it starts, before the `mount`
+    /// hooks run. Jobs sent with `ThreadPool::send_with_state` borrow it; it
+    /// shares its slot with `scratch`, which it replaces.
+    pub fn mount_with_state<F, S>(mut self, f: F) -> Self
+    where
+        F: Fn(&WorkerContext) -> S + Send + Sync + 'static,
+        S: Any,
+    {
+        self.instance.scratch = Some(Arc::new(move |worker| Box::new(f(worker)) as Box<dyn Any>));
+        self
+    }
+
+    /// Seeds the generator returned by `multix::rng` from the worker index.
+    pub fn seed_rng<F>(mut self, f: F) -> Self
+    where
+        F: Fn(usize) -> u64 + Send + Sync + 'static,
+    {
+        self.instance.seed_rng = Some(Arc::new(f));
+        self
+    }
+
+    /// Charges the job boxes made by `send_fn` and friends to `allocator`;
+    /// jobs it refuses are rejected with `SubmitError::AllocRefused`. Other
+    /// allocations of the pool, such as its stats and timer entries, are not
+    /// charged.
+    pub fn allocator<A: Allocator + 'static>(mut self, allocator: Arc<A>) -> Self {
+        self.instance.allocator = Some(allocator);
+        self
+    }
+
+    /// `f` maps the current stats to the fraction of `ThreadPool::offer`
+    /// submissions to drop, from 0.0 (none) to 1.0 (all).
+    pub fn shed<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&PoolStats) -> f64 + Send + Sync + 'static,
+    {
+        self.instance.shed = Some(Arc::new(f));
+        self
+    }
+
+    pub fn guard<G: JobGuard + 'static>(mut self, guard: G) -> Self {
+        self.instance.guard = Some(Arc::new(guard));
+        self
+    }
+
+    /// Wraps every job in `layer`. Layers added first run outermost. They run
+    /// on the worker inside the job's panic boundary, so a panic caught by a
+    /// layer does not count as one.
+    pub fn layer<L: JobLayer + 'static>(mut self, layer: L) -> Self {
+        self.instance.layers.push(Arc::new(layer));
+        self
+    }
+
+    /// Preallocates `count` payload buffers of `capacity` bytes for
+    /// `ThreadPool::claim_slot`.
+    pub fn slots(mut self, count: usize, capacity: usize) -> Self {
+        self.instance.slots = Some((count, capacity));
+        self
+    }
+
+    /// Calls `f` on each idle worker at most once per `interval`.
+    pub fn on_idle_tick<F>(mut self, interval: Duration, f: F) -> Self
+    where
+        F: Fn(&WorkerContext) + Send + Sync + 'static,
+    {
+        self.instance.idle_tick = Some((interval, Arc::new(f)));
+        self
+    }
+
+    /// Calls `f` on the worker after every job, as part of it. With
+    /// `ThreadPool<()>` jobs carry nothing and the pool becomes a way to wake
+    /// workers: each `send(())` runs `f` once.
+    pub fn handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&WorkerContext) + Send + Sync + 'static,
+    {
+        self.instance.handler = Some(Arc::new(f));
+        self
+    }
+
+    /// Called once if the queue disconnects while the pool is still running,
+    /// i.e. every handle was dropped without `close`. The pool then shuts
+    /// down on its own so that it still reaches `Terminated`.
+    pub fn on_disconnect<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.instance.on_disconnect = Some(Arc::new(f));
+        self
+    }
+
+    /// Called on the worker with the payload of every job that panics, and of
+    /// every panic outside a job that kills the worker, such as in a `mount`
+    /// hook. Such workers are replaced with a growing delay, until eight
+    /// have died in a row without a job finishing in between.
+    pub fn panic_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Box<dyn Any + Send>) + Send + Sync + 'static,
+    {
+        self.instance.panic_handler = Some(Arc::new(f));
+        self
+    }
+
+    /// Called on the worker for every job that finishes after its deadline.
+    pub fn on_deadline_miss<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&DeadlineMiss) + Send + Sync + 'static,
+    {
+        self.instance.on_deadline_miss = Some(Arc::new(f));
+        self
+    }
+
+    /// Records when every job was queued, picked up and finished, and hands
+    /// the timestamps to `sink` on the worker right after the job. Meant for
+    /// offline analysis, so nothing is aggregated.
+    pub fn record_timestamps<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(&JobTimestamps) + Send + Sync + 'static,
+    {
+        self.instance.timestamps = Some(Arc::new(sink));
+        self
+    }
+
+    /// Measures the CPU time of every job, reported in `KindStats::cpu` and
+    /// `JobOutcome::cpu`, where the platform keeps per-thread CPU clocks.
+    /// Costs two clock reads per job.
+    pub fn cpu_time(mut self) -> Self {
+        self.instance.cpu_time = true;
+        self
+    }
+
+    /// Records which thread sent every job and when, kept from when the job
+    /// is queued until it is dequeued and listed by
+    /// `ThreadPool::queue_snapshot`, to find out where jobs that never run
+    /// come from. With the `backtrace` feature the
+    /// sender's backtrace is captured as well, which is slow.
+    pub fn breadcrumbs(mut self) -> Self {
+        self.instance.breadcrumbs = true;
+        self
+    }
+
+    /// Called on the worker right before every job runs, in the job's
+    /// `JobContext`.
+    pub fn before_job<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&JobMeta) + Send + Sync + 'static,
+    {
+        self.instance.before_job = Some(Arc::new(f));
+        self
+    }
+
+    /// Called on the worker right after every job with how long it ran, even
+    /// if it panicked.
+    pub fn after_job<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&JobMeta, Duration) + Send + Sync + 'static,
+    {
+        self.instance.after_job = Some(Arc::new(f));
+        self
+    }
+
+    /// Queues jobs that the pool's own workers `send` ahead of jobs from
+    /// outside, so work already in progress finishes before new work starts.
+    pub fn boost_continuations(mut self) -> Self {
+        self.instance.boost_continuations = true;
+        self
+    }
+
+    /// How idle workers wait for jobs; they block right away by default.
+    pub fn idle_strategy<S: IdleStrategy + 'static>(mut self, strategy: S) -> Self {
+        self.instance.idle = Some(Arc::new(strategy));
+        self
+    }
+
+    /// Gives every worker a bump arena of `capacity` bytes, reset after each
+    /// job and reachable through `multix::arena`.
+    #[cfg(feature = "arena")]
+    pub fn arena(mut self, capacity: usize) -> Self {
+        self.instance.arena = Some(capacity);
+        self
+    }
+
+    /// Jobs are never run by the pool; external threads consume them with
+    /// `ThreadPool::recv` or `ThreadPool::iter`.
+    pub fn no_workers(mut self) -> Self {
+        self.instance.workers = false;
+        self
+    }
+
+    /// Injects the faults described by `policy`; see `FaultPolicy`.
+    #[cfg(feature = "fault-injection")]
+    pub fn fault_injection(mut self, policy: FaultPolicy) -> Self {
+        self.instance.faults = Some(policy);
+        self
+    }
+
+    /// Jobs read from `reader` are submitted to the first pool built from this
+    /// builder or any of its clones.
+    #[cfg(feature = "persistence")]
+    pub fn restore_from<T, R>(mut self, reader: R) -> io::Result<Self>
+    where
+        T: Job + DeserializeOwned,
+        R: Read,
+    {
+        let jobs: Vec<T> = crate::durable::read_jobs(reader)?;
+
+        self.restored = Arc::new(Mutex::new(Some(Box::new(jobs))));
+        Ok(self)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.instance.validate()?;
+
+        if !self.instance.workers && self.restored.lock().unwrap().is_some() {
+            return Err(ConfigError::ConflictingPolicies(
+                "no_workers",
+                "restore_from",
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn build<T: Job>(&self) -> ThreadPool<T> {
+        self.try_build().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_build<T: Job>(&self) -> Result<ThreadPool<T>, ConfigError> {
+        self.validate()?;
+        self.validate_restored::<T>()?;
+
+        let (tx, rx) = match self.instance.queue_capacity {
+            Some(QueueCapacity::Bounded(cap)) => bounded(cap),
+            Some(QueueCapacity::Unbounded) => unbounded(),
+            None => bounded(self.instance.size),
+        };
+        let termination_mutex = Mutex::new(());
+        let termination_signal = Condvar::new();
+        let (shutdown_tx, shutdown_rx) = bounded(0);
+        #[cfg(feature = "topology")]
+        let numa = self.instance.numa.then(Numa::detect).flatten();
+        #[cfg(feature = "topology")]
+        let nodes = numa.as_ref().map_or(0, |numa| numa.nodes.len());
+        #[cfg(not(feature = "topology"))]
+        let nodes = 0;
+        let locals = match self.instance.scheduler {
+            Scheduler::WorkStealing => {
+                let size = self.instance.size;
+
+                self.instance
+                    .max_size
+                    .map_or(size, |max| max.max(size))
+                    .max(1)
+            }
+            Scheduler::Shared => nodes,
+        };
+
+        let inner = Arc::new(Inner {
+            state: AtomicState::new(Lifecycle::Running),
+            active: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+            panicked: AtomicU64::new(0),
+            submitted: AtomicU64::new(0),
+            deadline_misses: AtomicU64::new(0),
+            progress: Mutex::new(Vec::new()),
+            size: AtomicUsize::new(self.instance.size),
+            next_id: AtomicU64::new(0),
+            next_worker: AtomicUsize::new(0),
+            shed_seq: AtomicU64::new(0),
+            crashes: AtomicUsize::new(0),
+            kinds: Mutex::new(HashMap::new()),
+            workers: Mutex::new(Vec::new()),
+            jobs: Arc::new(Registry::default()),
+            slots: self
+                .instance
+                .slots
+                .map(|(count, capacity)| Slots::new(count, capacity)),
+            reserved: AtomicUsize::new(0),
+            outcomes: unbounded(),
+            hooks: RwLock::new(Hooks::default()),
+            termination_mutex,
+            termination_signal,
+            shutdown_tx: Mutex::new(Some(shutdown_tx)),
+            shutdown_rx,
+            control: Control::new(),
+            timer: Timer::new(self.instance.name.clone()),
+            config: self.instance.clone(),
+            cpus: affinity::placement(self.instance.cores, &self.instance.pinning),
+            #[cfg(feature = "topology")]
+            numa,
+            locals,
+            #[cfg(feature = "fault-injection")]
+            faults: self.instance.faults.map(Injector::new),
+            children: Mutex::new(Vec::new()),
+            handles: AtomicUsize::new(1),
+            fork: Fork::default(),
+        });
+
+        let pool = ThreadPool {
+            inner,
+            tx,
+            rx,
+            lanes: Arc::new(Lanes::with_locals(locals)),
+            groups: Arc::new(RwLock::new(HashMap::new())),
+        };
+
+        if self.instance.spawn == SpawnPolicy::Eager {
+            pool.prestart_core_threads();
+        }
+
+        // Checked by `validate_restored`, so they fit and never block. Should
+        // the pool still refuse one, e.g. through fault injection, the jobs
+        // that have not started go back to the builder.
+        let restored = self.restored.lock().unwrap().take();
+        if let Some(restored) = restored {
+            if let Ok(jobs) = restored.downcast::<Vec<T>>() {
+                let mut jobs = jobs.into_iter();
+
+                while let Some(job) = jobs.next() {
+                    if let Err(err) = pool.try_send(job) {
+                        let mut kept = pool.close_force();
+                        kept.push(err.into_inner());
+                        kept.extend(jobs);
+                        *self.restored.lock().unwrap() = Some(Box::new(kept));
+
+                        return Err(ConfigError::RestoreRefused);
+                    }
+                }
+            }
+        }
+
+        Ok(pool)
+    }
+
+    fn validate_restored<T: Job>(&self) -> Result<(), ConfigError> {
+        let restored = self.restored.lock().unwrap();
+        let jobs = match restored.as_ref() {
+            Some(restored) => restored
+                .downcast_ref::<Vec<T>>()
+                .ok_or(ConfigError::RestoredJobType)?
+                .len(),
+            None => return Ok(()),
+        };
+        let capacity = match self.instance.queue_capacity {
+            Some(QueueCapacity::Bounded(capacity)) => capacity,
+            Some(QueueCapacity::Unbounded) => return Ok(()),
+            None => self.instance.size,
+        };
+
+        if jobs > capacity {
+            return Err(ConfigError::QueueSmallerThanBurst {
+                queue: capacity,
+                burst: jobs,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Job> ThreadPool<T> {
+    pub fn new(size: usize) -> ThreadPool<T> {
+        TPBuilder::new().size(size).build()
+    }
+
+    pub fn from_config(config: Config) -> ThreadPool<T> {
+        TPBuilder::from(config).build()
+    }
+
+    pub fn new_with_hooks<U, M>(size: usize, mount: U, unmount: M) -> ThreadPool<T>
+    where
+        U: Fn(&WorkerContext) + Send + Sync + 'static,
+        M: Fn(&WorkerContext) + Send + Sync + 'static,
+    {
+        TPBuilder::new()
+            .size(size)
+            .mount(mount)
+            .unmount(unmount)
+            .build()
+    }
+
+    pub fn single_thread() -> ThreadPool<T> {
+        TPBuilder::new().size(1).build()
+    }
+
+    pub fn add_mount_hook<F>(&self, f: F) -> HookHandle
+    where
+        F: Fn(&WorkerContext) + Send + Sync + 'static,
+    {
+        self.add_hook(HookKind::Mount, Arc::new(f))
+    }
+
+    pub fn add_unmount_hook<F>(&self, f: F) -> HookHandle
+    where
+        F: Fn(&WorkerContext) + Send + Sync + 'static,
+    {
+        self.add_hook(HookKind::Unmount, Arc::new(f))
+    }
+
+    fn add_hook(&self, kind: HookKind, hook: WorkerHook) -> HookHandle {
+        let id = self.inner.hooks.write().unwrap().add(kind, hook);
+
+        HookHandle {
+            inner: Arc::downgrade(&self.inner),
+            kind,
+            id,
+        }
+    }
+
+    /// Changes the core size. Growing starts workers for jobs already
+    /// queued and the rest on demand; shrinking retires surplus workers once
+    /// they finish their current job. The maximum set with `max_size` moves
+    /// by as much, keeping the same room for extra workers.
+    pub fn resize(&self, size: usize) -> Result<(), ConfigError> {
+        if size == 0 {
+            return Err(ConfigError::ZeroSize);
+        }
+
+        let old = self.inner.size.swap(size, Ordering::SeqCst);
+
+        if size > old && self.inner.config.spawn != SpawnPolicy::Manual {
+            while self.queued() > 0 && self.prestart_core_thread() {}
+        } else {
+            self.inner.control.notify_all();
+        }
+
+        Ok(())
+    }
+
+    pub fn prestart_core_thread(&self) -> bool {
+        if !self.inner.is_workers_overflow() {
+            self.inner
+                .add_worker(&self.rx, &self.lanes, None, &self.inner)
+                .is_ok()
+        } else {
+            false
+        }
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self.rx.try_recv(), Err(TryRecvError::Disconnected))
+    }
+
+    pub fn prestart_core_threads(&self) {
+        while self.prestart_core_thread() {}
+    }
+
+    pub fn close(&self) {
+        // A pool left without workers, e.g. under `SpawnPolicy::Manual`, gets
+        // one to run what is still queued before it terminates.
+        if self.inner.state.load().worker_count() == 0 && self.queued() > 0 {
+            let _ = self
+                .inner
+                .add_worker(&self.rx, &self.lanes, None, &self.inner);
+        }
+
+        self.inner.close();
+    }
+
+    /// Stops the pool without running the queued jobs, which are returned so
+    /// they can be persisted or sent elsewhere. Critical jobs are returned
+    /// too and are then the caller's to run.
+    pub fn close_force(&self) -> Vec<T> {
+        self.inner
+            .close_force(|| self.drain())
+            .into_iter()
+            .map(|task| task.job)
+            .collect()
+    }
+
+    /// Registers a producer for realtime threads with room for `capacity`
+    /// jobs, see `RtProducer`. It starts a thread forwarding its jobs to the
+    /// pool and counts as a handle while it lives.
+    pub fn rt_producer(&self, capacity: usize) -> RtProducer<T> {
+        rt::producer(self.clone(), self.inner.clone(), capacity)
+    }
+
+    /// Stops accepting jobs and waits up to `timeout` for the queue to drain.
+    /// Past the deadline the pool is closed with `close_force` and the queued
+    /// critical jobs run on the calling thread before it returns.
+    pub fn shutdown(&self, timeout: Duration) -> ShutdownResult {
+        self.close();
+
+        if self.await_termination_timeout(timeout) {
+            return ShutdownResult::Drained;
+        }
+
+        let (critical, abandoned): (Vec<_>, Vec<_>) = self
+            .inner
+            .close_force(|| self.drain())
+            .into_iter()
+            .partition(|task| task.critical);
+        let running = self.inner.active.load(Ordering::SeqCst);
+        let result = ShutdownResult::Forced {
+            abandoned: abandoned.len(),
+            critical: critical.len(),
+            running,
+        };
+        drop(abandoned);
+
+        for task in critical {
+            run_here(task);
+        }
+
+        result
+    }
+
+    /// Waits for the running jobs to finish and keeps the workers from
+    /// starting others until the guard is dropped, so no worker holds a lock
+    /// when the process forks; the timer's lock is held as well. The parent
+    /// drops the guard after the fork and the child hands it to
+    /// `reinit_after_fork`. Queued jobs are copied into the child and run in
+    /// both processes, the ones in the workers' LIFO slots included. Jobs
+    /// waiting in a scope for others are not waited for, they go on once the
+    /// guard is dropped. Must not be called from a job of the pool.
+    pub fn prepare_fork(&self) -> ForkGuard<'_> {
+        self.inner.fork.block();
+
+        ForkGuard {
+            fork: &self.inner.fork,
+            timer: Some(self.inner.timer.lock()),
+        }
+    }
+
+    /// Starts the pool over in a forked child, whose worker and timer threads
+    /// are left behind in the parent. Pools adopted with `adopt` and
+    /// realtime producers are not restarted.
+    pub fn reinit_after_fork(&self, mut guard: ForkGuard<'_>) {
+        self.inner.state.reset_worker_count();
+        self.inner.active.store(0, Ordering::SeqCst);
+        self.inner.workers.lock().unwrap().clear();
+
+        // Jobs left in the LIFO slots of the parent's workers are queued.
+        self.lanes.reset_slots();
+        for group in self.groups.read().unwrap().values() {
+            group.lanes.reset_slots();
+        }
+
+        if let Some(timer) = guard.timer.take() {
+            self.inner.timer.reinit_after_fork(timer);
+        }
+
+        // Dropping the guard would take `Fork::lock`, which a thread of the
+        // parent may have held as it forked. There is no one here to wake.
+        guard.fork.forking.store(false, Ordering::SeqCst);
+        mem::forget(guard);
+
+        if self.inner.config.spawn != SpawnPolicy::Manual {
+            self.prestart_core_threads();
+        }
+    }
+
+    pub fn is_terminating(&self) -> bool {
+        let state = self.inner.state.load();
+
+        state.is_shutdown() && !state.is_terminated()
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.inner.state.load().is_terminated()
+    }
+
+    pub fn await_termination(&self) {
+        self.inner.await_termination_until(None);
+    }
+
+    /// Waits up to `timeout` for the pool and its children to terminate.
+    /// Returns `false` if they are still running.
+    pub fn await_termination_timeout(&self, timeout: Duration) -> bool {
+        self.inner
+            .await_termination_until(Some(Instant::now() + timeout))
+    }
+
+    /// Ties `child` to the lifecycle of this pool. Once this pool terminates
+    /// its children are closed in the order they were adopted, and
+    /// `await_termination` waits for them too. `close_force` cascades
+    /// immediately.
+    pub fn adopt<U: Job>(&self, child: &ThreadPool<U>) {
+        let child: Arc<dyn Child> = Arc::new(ChildRef {
+            inner: child.inner.clone(),
+            rx: child.rx.clone(),
+            lanes: child.lanes.clone(),
+        });
+
+        self.inner.children.lock().unwrap().push(child.clone());
+
+        if self.inner.state.load().is_terminated() {
+            child.close();
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.inner.state.load().worker_count()
+    }
+
+    /// Starts `workers` threads that only run jobs sent with `send_to_group`
+    /// under `name`. They count against the pool size, so untagged jobs are
+    /// left with the remainder. Reserving an existing group adds workers to it.
+    pub fn reserve_group<S: Into<String>>(
+        &self,
+        name: S,
+        workers: usize,
+    ) -> Result<(), GroupTooLarge> {
+        let size = self.inner.size();
+        let reserved = self.inner.reserved.fetch_add(workers, Ordering::SeqCst);
+
+        if reserved + workers >= size {
+            self.inner.reserved.fetch_sub(workers, Ordering::SeqCst);
+
+            return Err(GroupTooLarge {
+                requested: workers,
+                available: size.saturating_sub(reserved + 1),
+            });
+        }
+
+        let mut groups = self.groups.write().unwrap();
+        let group = groups.entry(name.into()).or_insert_with(|| {
+            let (tx, rx) = unbounded();
+            let lanes = Arc::new(Lanes::new());
+            Group { tx, rx, lanes }
+        });
+
+        for _ in 0..workers {
+            self.inner
+                .add_dedicated_worker(&group.rx, &group.lanes, &self.inner);
+        }
+
+        Ok(())
+    }
+
+    pub fn send_to_group(&self, name: &str, job: T) -> Result<(), SubmitError<T>> {
+        if self.refuses() {
+            return Err(SubmitError::ShuttingDown(job));
+        }
+
+        match self.groups.read().unwrap().get(name) {
+            Some(group) => {
+                let mut task = self.inner.task(job);
+                let id = task.id;
+
+                task.queued();
+                group
+                    .tx
+                    .send(task)
+                    .map_err(|err| SubmitError::ShuttingDown(err.0.job))?;
+                self.inner.enqueued(id);
+
+                Ok(())
+            }
+            None => Err(SubmitError::UnknownGroup(job)),
+        }
+    }
+
+    /// Takes the next queued job, blocking until one arrives. Returns `None`
+    /// once the pool is shut down and its queues are empty. Jobs come in the
+    /// order workers take them, highest priority first.
+    pub fn recv(&self) -> Option<T> {
+        loop {
+            if self.inner.state.load().is_shutdown() {
+                return self.steal_task().map(|task| task.job);
+            }
+
+            match self
+                .lanes
+                .recv(&self.rx, None, &self.inner.shutdown_rx, None)
+            {
+                Ok(Some(task)) => return Some(task.job),
+                Ok(None) => {}
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Takes a queued job without waiting, for consumers outside the pool
+    /// with spare capacity. Nothing is taken once `close_force` has run.
+    pub fn steal(&self) -> Option<T> {
+        if self.inner.state.load().is_stoped() {
+            return None;
+        }
+
+        self.steal_task().map(|task| task.job)
+    }
+
+    fn steal_task(&self) -> Option<Task<T>> {
+        self.lanes.try_recv(&self.rx, None).ok()
+    }
+
+    pub fn steal_batch(&self, max: usize) -> Vec<T> {
+        iter::from_fn(|| self.steal()).take(max).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        iter::from_fn(move || self.recv())
+    }
+
+    /// Jobs waiting to run, the ones in the workers' LIFO slots included.
+    pub fn queued(&self) -> usize {
+        self.rx.len() + self.lanes.len()
+    }
+
+    // Takes every queued job out of the pool, its groups' and the workers'
+    // LIFO slots included.
+    fn drain(&self) -> Vec<Task<T>> {
+        let mut tasks = self.lanes.drain(&self.rx);
+
+        for group in self.groups.read().unwrap().values() {
+            tasks.extend(group.lanes.drain(&group.rx));
+        }
+
+        tasks
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let state = self.inner.state.load();
+        let workers = state.worker_count();
+        let active = self.inner.active.load(Ordering::SeqCst);
+        let panicked = self.inner.panicked.load(Ordering::SeqCst);
+
+        PoolStats {
+            lifecycle: state.lifecycle(),
+            size: self.inner.size(),
+            workers,
+            queued: self.queued(),
+            active,
+            idle: workers.saturating_sub(active),
+            submitted: self.inner.submitted.load(Ordering::SeqCst),
+            completed: self
+                .inner
+                .completed
+                .load(Ordering::SeqCst)
+                .saturating_sub(panicked),
+            panicked,
+        }
+    }
+
+    /// Counts of queued, running and finished jobs along with the fractions
+    /// reported by running jobs started with `send_with_progress`.
+    pub fn progress_summary(&self) -> ProgressSummary {
+        ProgressSummary {
+            queued: self.queued(),
+            in_flight: self.inner.active.load(Ordering::SeqCst),
+            completed: self.inner.completed.load(Ordering::SeqCst),
+            fractions: progress::fractions(&self.inner.progress),
+        }
+    }
+
+    /// Claims a free payload slot, or `None` if all slots are in use or the
+    /// pool was built without `TPBuilder::slots`.
+    pub fn claim_slot(&self) -> Option<Slot> {
+        self.inner.slots.as_ref()?.claim()
+    }
+
+    pub fn free_slots(&self) -> usize {
+        self.inner.slots.as_ref().map_or(0, Slots::free)
+    }
+
+    /// Jobs that finished after their deadline, over every kind.
+    pub fn deadline_misses(&self) -> u64 {
+        self.inner.deadline_misses.load(Ordering::SeqCst)
+    }
+
+    /// Totals for every job kind seen so far, including how long its jobs
+    /// waited in the queue; see `Job::kind`.
+    pub fn kind_stats(&self) -> HashMap<&'static str, KindStats> {
+        self.inner.kinds.lock().unwrap().clone()
+    }
+
+    /// Pool stats together with what each live worker and job kind did.
+    pub fn dump(&self) -> PoolDump {
+        let mut workers: Vec<WorkerStats> = self
+            .inner
+            .workers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|counters| counters.snapshot())
+            .collect();
+
+        workers.sort_by_key(|worker| worker.index);
+
+        PoolDump {
+            stats: self.stats(),
+            workers,
+            kinds: self.kind_stats(),
+        }
+    }
+
+    pub fn outcomes(&self) -> CCReceiver<JobOutcome> {
+        self.inner.outcomes.1.clone()
+    }
+
+    /// Returns a receiver that disconnects once the pool starts shutting down.
+    pub fn shutdown_receiver(&self) -> CCReceiver<()> {
+        self.inner.shutdown_rx.clone()
+    }
+
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        self.inner.timer.sleep(duration)
+    }
+
+    pub fn timeout<F: Future>(&self, duration: Duration, future: F) -> Timeout<F> {
+        Timeout::new(future, self.inner.timer.sleep(duration))
+    }
+
+    pub fn send(&self, job: T) -> Result<(), SubmitError<T>> {
+        self.send_task(self.inner.task(job)).map_err(unwrap_send)
+    }
+
+    // `send` for a task already made, handed back if the pool refuses it.
+    fn send_task(&self, task: Task<T>) -> Result<(), SubmitError<Task<T>>> {
+        if self.inner.config.boost_continuations && worker::is_worker_of(&self.inner) {
+            return self.push_lane(Priority::High, task);
+        }
+
+        if self.inner.config.scheduler == Scheduler::WorkStealing {
+            if let Some(index) = worker::current_index(&self.inner) {
+                return self.push_local(index % self.inner.locals, task);
+            }
+        }
+
+        #[cfg(feature = "topology")]
+        if let Some(node) = self.inner.numa.as_ref().and_then(Numa::current_node) {
+            return self.push_local(node, task);
+        }
+
+        self.push(task)
+    }
+
+    /// Sends a follow-up job from inside a job to the worker's LIFO slot, so
+    /// the same worker runs it next while its data is still in cache. A job
+    /// already in the slot is moved to the queue. Other threads than the
+    /// pool's workers get `send`.
+    pub fn send_local(&self, job: T) -> Result<(), SubmitError<T>> {
+        if !worker::is_worker_of(&self.inner) {
+            return self.send(job);
+        }
+
+        if self.refuses() {
+            return Err(SubmitError::ShuttingDown(job));
+        }
+
+        let mut task = self.inner.task(job);
+        task.queued();
+        self.inner.enqueued(task.id);
+
+        // The job it displaces was counted when it went into the slot, so it
+        // is queued directly, on the unbounded `High` lane if the queue is
+        // full.
+        if let Some(prev) = worker::replace_lifo(task) {
+            if let Err(err) = self.tx.try_send(prev) {
+                self.lanes.push(Priority::High, err.into_inner());
+            }
+
+            if self.inner.spawns_on_send() {
+                let _ = self
+                    .inner
+                    .add_worker(&self.rx, &self.lanes, None, &self.inner);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends a job the pool must not drop: discarding rejection policies run
+    /// it on the calling thread instead, and so does `shutdown` if it is
+    /// still queued when the deadline passes. `close_force` hands it back
+    /// like any queued job.
+    pub fn send_critical(&self, job: T) -> Result<(), SubmitError<T>> {
+        let task = Task {
+            critical: true,
+            ..self.inner.task(job)
+        };
+
+        self.push(task).map_err(unwrap_send)
+    }
+
+    /// Like `send`, but the job's progress can be looked up with `status`.
+    pub fn send_tracked(&self, job: T) -> Result<JobId, SubmitError<T>> {
+        let mut task = self.inner.task(job);
+        let id = task.id;
+
+        match task.tracker.as_mut() {
+            Some(tracker) => tracker.track(),
+            None => task.tracker = Some(self.inner.jobs.track(id)),
+        }
+
+        self.push(task).map_err(|err| {
+            let err = unwrap_send(err);
+            self.inner.jobs.forget(id);
+            err
+        })?;
+
+        Ok(JobId(id))
+    }
+
+    /// Status of a job sent with `send_tracked` or `Prepared::track`, or
+    /// `None` if it is unknown or was forgotten.
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        self.inner.jobs.status(id.0)
+    }
+
+    /// Breadcrumbs of the jobs still queued, oldest first. Empty unless the
+    /// pool was built with `TPBuilder::breadcrumbs`.
+    pub fn queue_snapshot(&self) -> Vec<Breadcrumb> {
+        self.inner.jobs.breadcrumbs()
+    }
+
+    /// Drops the status of a tracked job, which is otherwise kept for the
+    /// lifetime of the pool.
+    pub fn forget(&self, id: JobId) -> Option<JobStatus> {
+        self.inner.jobs.forget(id.0)
+    }
+
+    /// Queues `job` once `delay` has passed; see `send_at`.
+    pub fn send_after(&self, job: T, delay: Duration) -> Result<(), SubmitError<T>> {
+        self.send_at(job, Instant::now() + delay)
+    }
+
+    /// Hands `job` to the pool's timer thread, which queues it at `at` like
+    /// `send` would, so with `RejectionPolicy::Block` a full queue holds up
+    /// later timers too. The job is dropped if the pool is closed by then;
+    /// until then it keeps the pool from shutting down on disconnect.
+    pub fn send_at(&self, job: T, at: Instant) -> Result<(), SubmitError<T>> {
+        if self.refuses() {
+            return Err(SubmitError::ShuttingDown(job));
+        }
+
+        let delayed = Delayed {
+            pool: self.clone(),
+            task: Mutex::new(Some(self.inner.task(job))),
+        };
+
+        self.inner.timer.wake_at(at, Arc::new(delayed).into());
+
+        Ok(())
+    }
+
+    /// Queues a job built by `factory` every `interval`, starting one
+    /// interval from now, until the returned handle is cancelled or the pool
+    /// is closed. Ticks are spaced from the schedule, not from when the
+    /// previous job ran; if the timer falls behind by a whole interval, the
+    /// missed ticks are dropped and the schedule restarts from the late one.
+    /// Like `send_at`, a running recurrence keeps the pool from shutting down
+    /// on disconnect.
+    pub fn send_repeating<F>(&self, factory: F, interval: Duration) -> ScheduleHandle
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        assert!(
+            interval > Duration::ZERO,
+            "repeating interval must be positive"
+        );
+
+        let handle = ScheduleHandle::default();
+        let first = Instant::now() + interval;
+        let repeating = Repeating {
+            pool: self.clone(),
+            factory,
+            interval,
+            next: Mutex::new(first),
+            handle: handle.clone(),
+        };
+
+        self.inner.timer.wake_at(first, Arc::new(repeating).into());
+
+        handle
+    }
+
+    /// Queues `job` ahead of every job of a lower priority. Jobs of the same
+    /// priority run in the order they were sent. `Normal` is the same as
+    /// `send`; the other priorities are queued without a capacity limit.
+    pub fn send_with_priority(&self, job: T, priority: Priority) -> Result<(), SubmitError<T>> {
+        if priority == Priority::Normal {
+            return self.send(job);
+        }
+
+        self.push_lane(priority, self.inner.task(job))
+            .map_err(unwrap_send)
+    }
+
+    fn push_lane(&self, priority: Priority, task: Task<T>) -> Result<(), SubmitError<Task<T>>> {
+        if self.refuses() {
+            return Err(SubmitError::ShuttingDown(task));
+        }
+
+        self.queue_lane(priority, task);
+
+        Ok(())
+    }
+
+    /// Queues a job polling a spawned future on the `High` lane, which has
+    /// no capacity limit, so waking one never blocks, even from a worker of
+    /// a full pool. Only a closed pool hands the job back.
+    #[cfg(feature = "futures")]
+    pub(crate) fn send_wakeup(&self, job: T) -> Result<(), T> {
+        if self.inner.state.load().is_shutdown() {
+            return Err(job);
+        }
+
+        self.queue_lane(Priority::High, self.inner.task(job));
+
+        Ok(())
+    }
+
+    fn queue_lane(&self, priority: Priority, mut task: Task<T>) {
+        let id = task.id;
+
+        task.queued();
+        self.lanes.push(priority, task);
+        self.inner.enqueued(id);
+
+        if self.inner.spawns_on_send() {
+            let _ = self
+                .inner
+                .add_worker(&self.rx, &self.lanes, None, &self.inner);
+        }
+    }
+
+    fn push_local(&self, local: usize, mut task: Task<T>) -> Result<(), SubmitError<Task<T>>> {
+        if self.refuses() {
+            return Err(SubmitError::ShuttingDown(task));
+        }
+
+        let id = task.id;
+
+        task.queued();
+        self.lanes.push_local(local, task);
+        self.inner.enqueued(id);
+
+        if self.inner.spawns_on_send() {
+            let _ = self
+                .inner
+                .add_worker(&self.rx, &self.lanes, None, &self.inner);
+        }
+
+        Ok(())
+    }
+
+    /// Like `send`, but once the pool has all of its workers the submission is
+    /// a single state load followed by the channel push, skipping the
+    /// worker-spawn bookkeeping `send` does on every call.
+    pub fn send_fast(&self, job: T) -> Result<(), SubmitError<T>> {
+        self.push_fast(self.inner.task(job)).map_err(unwrap_send)
+    }
+
+    pub fn sender(&self) -> PoolSender<T> {
+        PoolSender::new(self.clone())
+    }
+
+    pub(crate) fn send_leased(&self, job: T, lease: Option<Lease>) -> Result<(), SubmitError<T>> {
+        let task = Task {
+            lease,
+            ..self.inner.task(job)
+        };
+
+        self.push(task).map_err(unwrap_send)
+    }
+
+    /// Builds the task envelope for `job` without queueing it, so the work
+    /// done before the push can happen outside the caller's critical section.
+    pub fn prepare(&self, job: T) -> Prepared<'_, T> {
+        Prepared {
+            pool: self,
+            task: self.inner.task(job),
+        }
+    }
+
+    /// Submits a non-critical job, which the `TPBuilder::shed` policy may drop
+    /// as the pool approaches saturation.
+    pub fn offer(&self, job: T) -> Result<(), SubmitError<T>> {
+        if self.refuses() {
+            return Err(SubmitError::ShuttingDown(job));
+        }
+
+        if let Some(shed) = self.inner.config.shed.as_ref() {
+            let fraction = shed(&self.stats());
+
+            if fraction > 0.0 && self.inner.shed_roll() < fraction {
+                return Err(SubmitError::Shed(job));
+            }
+        }
+
+        self.send(job)
+    }
+
+    /// Queues `job`, or runs it on the calling thread if the pool is shutting
+    /// down, so the work is never lost. Returns `true` if the job was queued.
+    /// An inline job runs like on a worker: hooks and stats see it, and its
+    /// panic goes to the panic handler rather than the caller.
+    pub fn send_or_run_inline(&self, job: T) -> bool {
+        match self.send_task(self.inner.task(job)) {
+            Ok(()) => true,
+            Err(err) => {
+                worker::run_inline(err.into_inner(), &self.rx, &self.lanes, &self.inner);
+                false
+            }
+        }
+    }
+
+    pub fn send_with_context(&self, job: T, context: JobContext) -> Result<(), SubmitError<T>> {
+        let task = Task {
+            context: Some(context),
+            ..self.inner.task(job)
+        };
+
+        self.push(task).map_err(unwrap_send)
+    }
+
+    pub fn send_reporting(&self, job: T) -> Result<u64, SubmitError<T>> {
+        let task = Task {
+            report: true,
+            ..self.inner.task(job)
+        };
+        let id = task.id;
+
+        self.push(task).map(|_| id).map_err(unwrap_send)
+    }
+
+    pub fn send_reporting_named<S>(&self, job: T, name: S) -> Result<u64, SubmitError<T>>
+    where
+        S: Into<String>,
+    {
+        let task = Task {
+            report: true,
+            name: Some(name.into()),
+            ..self.inner.task(job)
+        };
+        let id = task.id;
+
+        self.push(task).map(|_| id).map_err(unwrap_send)
+    }
+
+    pub fn send_timeout(&self, job: T, timeout: Duration) -> Result<(), SubmitError<T>> {
+        self.push_timeout(self.inner.task(job), timeout)
+            .map_err(unwrap_send)
+    }
+
+    pub fn try_send(&self, job: T) -> Result<(), SubmitError<T>> {
+        self.try_push(self.inner.task(job)).map_err(unwrap_send)
+    }
+
+    // The check every send makes before queueing anything.
+    fn refuses(&self) -> bool {
+        self.inner.state.load().is_shutdown() || self.inner.fail_send()
+    }
+
+    fn push(&self, task: Task<T>) -> Result<(), SubmitError<Task<T>>> {
+        match self.try_push(task) {
+            Err(SubmitError::Full(task)) => self.reject(task),
+            result => result,
+        }
+    }
+
+    fn reject(&self, task: Task<T>) -> Result<(), SubmitError<Task<T>>> {
+        match self.inner.config.rejection {
+            RejectionPolicy::Abort => Err(SubmitError::Full(task)),
+            RejectionPolicy::Block => self.wait_push(task, None),
+            RejectionPolicy::CallerRuns => {
+                run_here(task);
+
+                Ok(())
+            }
+            // Critical jobs are never discarded; they run on the caller.
+            RejectionPolicy::Discard => {
+                if task.critical {
+                    run_here(task);
+                }
+
+                Ok(())
+            }
+            RejectionPolicy::DiscardOldest => {
+                let mut task = task;
+
+                // A rendezvous queue never holds a job to discard, so the job
+                // goes back to the caller; `validate` refuses such pools.
+                if self.tx.capacity() == Some(0) {
+                    return Err(SubmitError::Full(task));
+                }
+
+                loop {
+                    if let Ok(oldest) = self.rx.try_recv() {
+                        if oldest.critical {
+                            run_here(oldest);
+                        }
+                    }
+
+                    task = match self.try_push(task) {
+                        Ok(()) => return Ok(()),
+                        Err(SubmitError::Full(task)) => task,
+                        Err(err) => return Err(err),
+                    };
+                }
+            }
+        }
+    }
+
+    fn push_fast(&self, mut task: Task<T>) -> Result<(), SubmitError<Task<T>>> {
+        let state = self.inner.state.load();
+
+        if state.is_shutdown() || self.inner.fail_send() {
+            return Err(SubmitError::ShuttingDown(task));
+        }
+
+        if state.worker_count() >= self.inner.worker_limit() {
+            let id = task.id;
+
+            task.queued();
+            match self.tx.try_send(task) {
+                Ok(()) => {
+                    self.inner.enqueued(id);
+
+                    Ok(())
+                }
+                Err(TrySendError::Full(task)) => self.reject(task),
+                Err(TrySendError::Disconnected(task)) => Err(SubmitError::ShuttingDown(task)),
+            }
+        } else {
+            self.push(task)
+        }
+    }
+
+    fn push_timeout(&self, task: Task<T>, timeout: Duration) -> Result<(), SubmitError<Task<T>>> {
+        match self.try_push(task) {
+            Err(SubmitError::Full(task)) => self.wait_push(task, Some(timeout)),
+            result => result,
+        }
+    }
+
+    // Blocks until the queue has room, giving up as soon as shutdown starts
+    // rather than leaving the job in a queue nobody will drain.
+    fn wait_push(
+        &self,
+        task: Task<T>,
+        timeout: Option<Duration>,
+    ) -> Result<(), SubmitError<Task<T>>> {
+        let mut select = Select::new();
+        let send = select.send(&self.tx);
+        let shutdown = select.recv(&self.inner.shutdown_rx);
+
+        let oper = match timeout {
+            Some(timeout) => match select.select_timeout(timeout) {
+                Ok(oper) => oper,
+                Err(_) => return Err(SubmitError::Timeout(task)),
+            },
+            None => select.select(),
+        };
+
+        if oper.index() == send {
+            let id = task.id;
+
+            oper.send(&self.tx, task)
+                .map_err(|SendError(task)| SubmitError::ShuttingDown(task))?;
+            self.inner.enqueued(id);
+
+            Ok(())
+        } else {
+            debug_assert_eq!(shutdown, oper.index());
+            let _ = oper.recv(&self.inner.shutdown_rx);
+
+            Err(SubmitError::ShuttingDown(task))
+        }
+    }
+
+    fn try_push(&self, mut task: Task<T>) -> Result<(), SubmitError<Task<T>>> {
+        if self.refuses() {
+            return Err(SubmitError::ShuttingDown(task));
+        }
+
+        let id = task.id;
+
+        task.queued();
+
+        match self.tx.try_send(task) {
+            Ok(_) => {
+                self.inner.enqueued(id);
+
+                if self.tx.capacity() != Some(0) && self.inner.spawns_on_send() {
+                    let _ = self
+                        .inner
+                        .add_worker(&self.rx, &self.lanes, None, &self.inner);
+                }
+
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(task)) => Err(SubmitError::ShuttingDown(task)),
+            Err(TrySendError::Full(task)) if self.inner.config.spawn == SpawnPolicy::Manual => {
+                Err(SubmitError::Full(task))
+            }
+            Err(TrySendError::Full(task)) => {
+                match self
+                    .inner
+                    .add_worker(&self.rx, &self.lanes, Some(task), &self.inner)
+                {
+                    Ok(_) => {
+                        self.inner.enqueued(id);
+
+                        Ok(())
+                    }
+                    Err(task) => Err(SubmitError::Full(task.unwrap())),
+                }
+            }
+        }
+    }
+}
+
+fn run_here<T: Job>(task: Task<T>) {
+    let prev = JobContext::enter(task.context);
+    task.job.call();
+    JobContext::enter(prev);
+}
+
+fn unwrap_send<T>(err: SubmitError<Task<T>>) -> SubmitError<T> {
+    err.map(|task| task.job)
+}
+
+#[cfg(feature = "persistence")]
+impl<T: Job + Serialize> ThreadPool<T> {
+    /// Takes every queued job out of the pool, from all of its queues, and
+    /// writes them to `writer` for `TPBuilder::restore_from`. Returns how
+    /// many there were. The jobs no longer run in this pool.
+    pub fn checkpoint<W: Write>(&self, writer: W) -> io::Result<usize> {
+        let jobs: Vec<T> = self.drain().into_iter().map(|task| task.job).collect();
+
+        crate::durable::write_jobs(writer, &jobs)?;
+        Ok(jobs.len())
+    }
+}
+
+impl ThreadPool<Box<dyn JobBox>> {
+    /// Runs `f` on the pool and returns a handle to its return value. If the
+    /// pool refuses the job the handle resolves to `JobError::Cancelled`.
+    pub fn submit<F, R>(&self, f: F) -> JobHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (job, handle) = handle::wrap(f);
+        let _ = self.send_fn(job);
+
+        handle
+    }
+
+    /// Runs `future` on the pool. Every wakeup polls it once on a worker,
+    /// and the returned handle resolves to its output.
+    #[cfg(feature = "futures")]
+    pub fn spawn_future<F>(&self, future: F) -> JobHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        executor::spawn(self, future)
+    }
+
+    /// Like `submit`, but `f` also gets a `ProgressSink` whose reports arrive
+    /// on the returned `ProgressReceiver`.
+    pub fn send_with_progress<F, P, R>(&self, f: F) -> (JobHandle<R>, ProgressReceiver<P>)
+    where
+        F: FnOnce(&ProgressSink<P>) -> R + Send + 'static,
+        P: Send + 'static,
+        R: Send + 'static,
+    {
+        let (sink, progress) = progress::channel(&self.inner.progress);
+        let handle = self.submit(move || f(&sink));
+
+        (handle, progress)
+    }
+
+    /// Runs `f` with a `Scope` whose jobs may borrow from the caller's stack
+    /// and blocks until every job sent through it has finished. Panics if
+    /// `f` or any of those jobs panicked.
+    ///
+    /// Called from a job of the same pool, scoped jobs the queue has no room
+    /// for run right away and the worker runs queued jobs while it waits, so
+    /// it does not deadlock when every worker is busy. Jobs may send more
+    /// jobs through the scope to split work recursively.
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        scope::scope(self, f)
+    }
+
+    /// Like `scope`, but instead of panicking it waits for every job and
+    /// returns how many completed along with the errors of the others.
+    pub fn region<'env, F>(&self, f: F) -> RegionResult
+    where
+        F: for<'scope> FnOnce(&'scope Region<'scope, 'env>),
+    {
+        scope::region(self, PanicPolicy::Continue, f)
+    }
+
+    /// Like `region`, with `policy` deciding what happens to the other jobs
+    /// once one of them panics.
+    pub fn region_with<'env, F>(&self, policy: PanicPolicy, f: F) -> RegionResult
+    where
+        F: for<'scope> FnOnce(&'scope Region<'scope, 'env>),
+    {
+        scope::region(self, policy, f)
+    }
+
+    /// Runs `a` on the calling thread and `b` on the pool, and returns both
+    /// results once they are done. Called from a worker of the pool, `b`
+    /// runs on that worker as well if no other one takes it first. Both may
+    /// borrow from the caller's stack and call `join` again, see `scope`.
+    /// Panics if either panicked.
+    pub fn join<A, B, RA, RB>(&self, a: A, b: B) -> (RA, RB)
+    where
+        A: FnOnce() -> RA + Send,
+        B: FnOnce() -> RB + Send,
+        RB: Send,
+    {
+        let mut rb = None;
+        let ra = self.scope(|scope| {
+            scope.send(|| rb = Some(b()));
+            a()
+        });
+
+        (ra, rb.expect("scope returned before `b` ran"))
+    }
 
-impl TPBuilder {
-    pub fn new() -> TPBuilder {
-        let num_cpus = num_cpus::get();
+    /// Sends a job of a scope. From the pool's own workers it never waits for
+    /// room in the queue, which might never come, and hands the job back.
+    pub(crate) fn send_scoped<F>(&self, job: F) -> Result<(), Box<dyn JobBox>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let job = self.boxed(job)?;
 
-        TPBuilder {
-            instance: Config {
-                size: num_cpus,
-                timeout: None,
-                stack_size: None,
-                mount: None,
-                unmount: None,
-            },
+        if worker::is_worker_of(&self.inner) {
+            self.try_send(job).map_err(SubmitError::into_inner)
+        } else {
+            self.send(job).map_err(SubmitError::into_inner)
         }
     }
 
-    pub fn size(mut self, val: usize) -> Self {
-        self.instance.size = val;
-        self
+    /// Runs a queued job if the calling thread is one of the pool's workers.
+    pub(crate) fn run_queued(&self) -> bool {
+        worker::run_queued(&self.rx, &self.lanes, &self.inner)
     }
 
-    pub fn timeout(mut self, val: Duration) -> Self {
-        self.instance.timeout = Some(val);
-        self
+    /// Stops counting the calling worker's job as running for
+    /// `prepare_fork` until the guard drops, while it waits on other jobs.
+    pub(crate) fn pause(&self) -> Option<worker::Paused> {
+        worker::Paused::new(&self.inner)
     }
 
-    pub fn stack_size(mut self, val: usize) -> Self {
-        self.instance.stack_size = Some(val);
-        self
+    pub(crate) fn is_worker(&self) -> bool {
+        worker::is_worker_of(&self.inner)
     }
 
-    pub fn mount<F>(mut self, f: F) -> Self
+    fn boxed<F>(&self, job: F) -> Result<Box<dyn JobBox>, Box<dyn JobBox>>
     where
-        F: Fn() + Send + Sync + 'static,
+        F: FnOnce() + Send + 'static,
     {
-        self.instance.mount = Some(Arc::new(f));
-        self
+        alloc::boxed(self.inner.config.allocator.as_ref(), job)
+            .map_err(|job| Box::new(job) as Box<dyn JobBox>)
     }
 
-    pub fn unmount<F>(mut self, f: F) -> Self
+    pub fn send_fn<F>(&self, job: F) -> Result<(), SubmitError<Box<dyn JobBox>>>
     where
-        F: Fn() + Send + Sync + 'static,
+        F: FnOnce() + Send + 'static,
     {
-        self.instance.unmount = Some(Arc::new(f));
-        self
+        self.send(self.boxed(job).map_err(SubmitError::AllocRefused)?)
     }
 
-    pub fn build<T: Job>(self) -> ThreadPool<T> {
-        assert!(self.instance.size >= 1, "at least one thread required");
-
-        let (tx, rx) = bounded(self.instance.size);
-        let termination_mutex = Mutex::new(());
-        let termination_signal = Condvar::new();
-
-        let inner = Arc::new(Inner {
-            state: AtomicState::new(Lifecycle::Running),
-            termination_mutex,
-            termination_signal,
-            config: self.instance,
-        });
-
-        let pool = ThreadPool {
-            inner,
-            tx,
-            rx,
-        };
+    /// Runs `job` with the state of the worker it lands on, see
+    /// `TPBuilder::mount_with_state`. The job panics if the workers have no
+    /// state of type `S`.
+    pub fn send_with_state<S, F>(&self, job: F) -> Result<(), SubmitError<Box<dyn JobBox>>>
+    where
+        S: Any,
+        F: FnOnce(&mut S) + Send + 'static,
+    {
+        self.send_fn(move || {
+            let mut state = scratch::scratch::<S>().expect("worker has no state of this type");
 
-        pool
+            job(&mut state)
+        })
     }
-}
 
-impl<T: Job> ThreadPool<T> {
-    pub fn new(size: usize) -> ThreadPool<T> {
-        TPBuilder::new().size(size).build()
+    /// Queues only `factory`, which builds the job on the worker right before
+    /// it runs. With a deep backlog this keeps large payloads out of the
+    /// queue, and the built job need not be `Send`.
+    pub fn send_lazy<F, J>(&self, factory: F) -> Result<(), SubmitError<Box<dyn JobBox>>>
+    where
+        F: FnOnce() -> J + Send + 'static,
+        J: FnOnce(),
+    {
+        self.send_fn(move || factory()())
     }
 
-    pub fn new_with_hooks<U, M>(size: usize, mount: U, unmount: M) -> ThreadPool<T>
+    pub fn send_fn_timeout<F>(
+        &self,
+        job: F,
+        timeout: Duration,
+    ) -> Result<(), SubmitError<Box<dyn JobBox>>>
     where
-        U: Fn() + Send + Sync + 'static,
-        M: Fn() + Send + Sync + 'static,
+        F: FnOnce() + Send + 'static,
     {
-        TPBuilder::new()
-            .size(size)
-            .mount(mount)
-            .unmount(unmount)
-            .build()
+        self.send_timeout(self.boxed(job).map_err(SubmitError::AllocRefused)?, timeout)
     }
 
-    pub fn single_thread() -> ThreadPool<T> {
-        TPBuilder::new().size(1).build()
+    pub fn try_send_fn<F>(&self, job: F) -> Result<(), SubmitError<Box<dyn JobBox>>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.try_send(self.boxed(job).map_err(SubmitError::AllocRefused)?)
     }
+}
 
-    pub fn prestart_core_thread(&self) -> bool {
-        if !self.inner.is_workers_overflow() {
-            self.inner.add_worker(&self.rx, None, &self.inner).is_ok()
-        } else {
-            false
-        }
+impl<'a, T: Job> Prepared<'a, T> {
+    pub fn id(&self) -> u64 {
+        self.task.id
     }
 
-    pub fn is_disconnected(&self) -> bool {
-        match self.rx.try_recv() {
-            Err(TryRecvError::Disconnected) => true,
-            _ => false,
-        }
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.task.name = Some(name.into());
+        self
     }
 
-    pub fn prestart_core_threads(&self) {
-        while self.prestart_core_thread() {}
+    pub fn context(mut self, context: JobContext) -> Self {
+        self.task.context = Some(context);
+        self
     }
 
-    pub fn close(&self) {
-        drop(&self.tx);
+    /// Counts the job as a deadline miss if it finishes after `deadline`;
+    /// see `ThreadPool::deadline_misses`. It does not change when it runs.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.task.deadline = Some(deadline);
+        self
     }
 
-    pub fn close_force(&self) {
-        drop(&self.tx);
-        drop(&self.rx);
-
-        if self.inner.state.try_transition_to_stop() {
-            loop {
-                match self.rx.recv() {
-                    Err(_) => return,
-                    Ok(_) => {}
-                }
-            }
+    /// Tracks the job's status under `JobId(self.id())`; see
+    /// `ThreadPool::status`.
+    pub fn track(mut self) -> Self {
+        match self.task.tracker.as_mut() {
+            Some(tracker) => tracker.track(),
+            None => self.task.tracker = Some(self.pool.inner.jobs.track(self.task.id)),
         }
+        self
     }
 
-    pub fn is_terminating(&self) -> bool {
-        self.is_disconnected() && !self.is_terminated()
+    pub fn reporting(mut self) -> Self {
+        self.task.report = true;
+        self
     }
 
-    pub fn is_terminated(&self) -> bool {
-        self.inner.state.load().is_terminated()
+    pub fn commit(mut self) -> Result<(), SubmitError<T>> {
+        self.task.queued_at = Instant::now();
+        self.pool.push_fast(self.task).map_err(unwrap_send)
+    }
+}
+
+impl<T: Job> Executor<T> for ThreadPool<T> {
+    fn execute(&self, job: T) -> Result<(), SendError<T>> {
+        self.send(job).map_err(|err| SendError(err.into_inner()))
     }
+}
 
-    pub fn await_termination(&self) {
-        let mut lock = self.inner.termination_mutex.lock().unwrap();
+impl<T> Clone for ThreadPool<T> {
+    fn clone(&self) -> Self {
+        self.inner.handles.fetch_add(1, Ordering::Relaxed);
 
-        while !self.inner.state.load().is_terminated() {
-            lock = self.inner.termination_signal.wait(lock).unwrap();
+        ThreadPool {
+            inner: self.inner.clone(),
+            tx: self.tx.clone(),
+            rx: self.rx.clone(),
+            lanes: self.lanes.clone(),
+            groups: self.groups.clone(),
         }
     }
+}
 
-    pub fn size(&self) -> usize {
-        self.inner.state.load().worker_count()
+// Workers only hold the `Inner` and the receiver, so the last handle owns the
+// last sender. Dropping it disconnects the queue and the pool drains and shuts
+// down without waiting for a worker to notice.
+impl<T> Drop for ThreadPool<T> {
+    fn drop(&mut self) {
+        if self.inner.handles.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.inner.disconnected();
+        }
     }
+}
 
-    pub fn queued(&self) -> usize {
-        self.rx.len()
+impl<T: Job> fmt::Debug for ThreadPool<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let stats = self.stats();
+
+        fmt.debug_struct("ThreadPool")
+            .field("lifecycle", &stats.lifecycle)
+            .field("size", &stats.size)
+            .field("workers", &stats.workers)
+            .field("queued", &stats.queued)
+            .field("active", &stats.active)
+            .finish()
     }
+}
 
-    pub fn send(&self, job: T) -> Result<(), SendError<T>> {
-        match self.try_send(job) {
-            Ok(_) => Ok(()),
-            Err(TrySendError::Disconnected(job)) => Err(SendError(job)),
-            Err(TrySendError::Full(job)) => self.tx.send(job),
+impl Hooks {
+    fn slot(&mut self, kind: HookKind) -> &mut Vec<(u64, WorkerHook)> {
+        match kind {
+            HookKind::Mount => &mut self.mount,
+            HookKind::Unmount => &mut self.unmount,
         }
     }
 
-    pub fn send_timeout(&self, job: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
-        match self.try_send(job) {
-            Ok(_) => Ok(()),
-            Err(TrySendError::Disconnected(job)) => Err(SendTimeoutError::Disconnected(job)),
-            Err(TrySendError::Full(job)) => self.tx.send_timeout(job, timeout),
-        }
+    fn add(&mut self, kind: HookKind, hook: WorkerHook) -> u64 {
+        let id = self.next_id;
+
+        self.next_id += 1;
+        self.slot(kind).push((id, hook));
+        id
     }
 
-    pub fn try_send(&self, job: T) -> Result<(), TrySendError<T>> {
-        match self.tx.try_send(job) {
-            Ok(_) => {
-                if !self.inner.is_workers_overflow() {
-                    let _ = self.inner.add_worker(&self.rx, None, &self.inner);
-                }
+    fn remove(&mut self, kind: HookKind, id: u64) {
+        self.slot(kind).retain(|&(hook_id, _)| hook_id != id);
+    }
 
-                Ok(())
-            }
-            Err(TrySendError::Disconnected(job)) => {
-                return Err(TrySendError::Disconnected(job));
-            }
-            Err(TrySendError::Full(job)) => match self.inner.add_worker(&self.rx, Some(job), &self.inner) {
-                Ok(_) => return Ok(()),
-                Err(job) => return Err(TrySendError::Full(job.unwrap())),
-            },
-        }
+    fn list(&self, kind: HookKind) -> Vec<WorkerHook> {
+        let hooks = match kind {
+            HookKind::Mount => &self.mount,
+            HookKind::Unmount => &self.unmount,
+        };
+
+        hooks.iter().map(|(_, hook)| hook.clone()).collect()
     }
 }
 
-impl ThreadPool<Box<JobBox>> {
-    pub fn send_fn<F>(&self, job: F) -> Result<(), SendError<Box<JobBox>>>
-    where
-        F: FnOnce() + Send + 'static,
-    {
-        let job: Box<JobBox> = Box::new(job);
-        self.send(job)
+impl HookHandle {
+    pub fn kind(&self) -> HookKind {
+        self.kind
     }
 
-    pub fn send_fn_timeout<F>(
-        &self,
-        job: F,
-        timeout: Duration,
-    ) -> Result<(), SendTimeoutError<Box<JobBox>>>
-    where
-        F: FnOnce() + Send + 'static,
-    {
-        let job: Box<JobBox> = Box::new(job);
-        self.send_timeout(job, timeout)
+    pub fn remove(self) {
+        drop(self);
     }
 
-    pub fn try_send_fn<F>(&self, job: F) -> Result<(), TrySendError<Box<JobBox>>>
-    where
-        F: FnOnce() + Send + 'static,
-    {
-        let job: Box<JobBox> = Box::new(job);
-        self.try_send(job)
+    pub fn forget(self) {
+        mem::forget(self);
     }
 }
 
-impl<T> Clone for ThreadPool<T> {
-    fn clone(&self) -> Self {
-        ThreadPool {
-            inner: self.inner.clone(),
-            tx: self.tx.clone(),
-            rx: self.rx.clone(),
+impl Drop for HookHandle {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.upgrade() {
+            inner.hooks.write().unwrap().remove(self.kind, self.id);
         }
     }
 }
 
-impl<T: Job> fmt::Debug for ThreadPool<T> {
+impl fmt::Debug for HookHandle {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("HookHandle")
+            .field("kind", &self.kind)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl<T: Job> fmt::Display for ThreadPool<T> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        fmt.debug_struct("ThreadPool").finish()
+        let stats = self.stats();
+
+        match self.inner.config.name {
+            Some(ref name) => write!(fmt, "pool {:?} ", name)?,
+            None => write!(fmt, "pool ")?,
+        }
+
+        write!(
+            fmt,
+            "[{}] workers={}/{} queued={} inflight={}",
+            stats.lifecycle, stats.workers, stats.size, stats.queued, stats.active
+        )
     }
 }
 
 impl Inner {
-    fn add_worker<T: Job>(&self, rx: &CCReceiver<T>, job: Option<T>, arc: &Arc<Inner>) -> Result<(), Option<T>> {
+    pub fn task<T>(&self, job: T) -> Task<T> {
+        let mut task = Task::new(self.next_id.fetch_add(1, Ordering::Relaxed), job);
+
+        if self.config.breadcrumbs {
+            task.tracker = Some(self.jobs.trail(task.id));
+        }
+
+        task
+    }
+
+    pub(crate) fn add_worker<T: Job>(
+        &self,
+        rx: &CCReceiver<Task<T>>,
+        lanes: &Arc<Lanes<T>>,
+        job: Option<Task<T>>,
+        arc: &Arc<Inner>,
+    ) -> Result<(), Option<Task<T>>> {
         let mut state = self.state.load();
+        // Workers past the core size are only started to take a job the full
+        // queue refused.
+        let limit = match job {
+            Some(_) => self.worker_limit(),
+            None => self.size(),
+        };
 
         'retry: loop {
             let lifecycle = state.lifecycle();
 
-            if state.is_stoped() {
+            if state.is_shutdown() || !self.config.workers {
                 return Err(job);
             }
 
             loop {
                 let wc = state.worker_count();
 
-                if wc >= CAPACITY || wc >= self.config.size {
+                if wc >= CAPACITY || wc >= limit {
                     return Err(job);
                 }
 
@@ -318,8 +2563,11 @@ impl Inner {
         }
 
         let worker = Worker {
+            index: self.next_worker.fetch_add(1, Ordering::SeqCst),
             rx: rx.clone(),
+            lanes: lanes.clone(),
             inner: arc.clone(),
+            dedicated: false,
         };
 
         worker.spawn(job);
@@ -327,17 +2575,227 @@ impl Inner {
         Ok(())
     }
 
+    pub(crate) fn add_dedicated_worker<T: Job>(
+        &self,
+        rx: &CCReceiver<Task<T>>,
+        lanes: &Arc<Lanes<T>>,
+        arc: &Arc<Inner>,
+    ) -> bool {
+        let mut state = self.state.load();
+
+        loop {
+            if state.is_shutdown() || state.worker_count() >= CAPACITY {
+                return false;
+            }
+
+            state = match self.state.compare_and_inc_worker_count(state) {
+                Ok(_) => break,
+                Err(state) => state,
+            };
+        }
+
+        let worker = Worker {
+            index: self.next_worker.fetch_add(1, Ordering::SeqCst),
+            rx: rx.clone(),
+            lanes: lanes.clone(),
+            inner: arc.clone(),
+            dedicated: true,
+        };
+
+        worker.spawn(None);
+
+        true
+    }
+
+    fn enqueued(&self, id: u64) {
+        self.submitted.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(target: "multix", job = id, pool = ?self.config.name, "job enqueued");
+        #[cfg(not(feature = "tracing"))]
+        let _ = id;
+    }
+
+    fn spawns_on_send(&self) -> bool {
+        self.config.spawn != SpawnPolicy::Manual && !self.is_workers_overflow()
+    }
+
+    #[cfg(feature = "fault-injection")]
+    fn fail_send(&self) -> bool {
+        self.faults.as_ref().is_some_and(Injector::fail_send)
+    }
+
+    #[cfg(not(feature = "fault-injection"))]
+    fn fail_send(&self) -> bool {
+        false
+    }
+
+    fn shed_roll(&self) -> f64 {
+        let seq = self.shed_seq.fetch_add(rng::GAMMA, Ordering::Relaxed);
+
+        rng::unit(rng::mix(seq.wrapping_add(rng::GAMMA)))
+    }
+
+    pub fn mount(&self, worker: &WorkerContext) {
+        if let Some(f) = self.config.mount.as_ref() {
+            f(worker);
+        }
+
+        let hooks = self.hooks.read().unwrap().list(HookKind::Mount);
+        for f in hooks {
+            f(worker);
+        }
+    }
+
+    pub fn unmount(&self, worker: &WorkerContext) {
+        let hooks = self.hooks.read().unwrap().list(HookKind::Unmount);
+        for f in hooks {
+            f(worker);
+        }
+
+        if let Some(f) = self.config.unmount.as_ref() {
+            f(worker);
+        }
+    }
+
     pub fn is_workers_overflow(&self) -> bool {
         let state = self.state.load();
 
-        state.worker_count() >= self.config.size
+        state.worker_count() >= self.size()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::SeqCst)
+    }
+
+    /// Local queue worker `index` takes from first, see `Lanes`.
+    pub(crate) fn local_queue(&self, index: usize) -> Option<usize> {
+        match self.config.scheduler {
+            Scheduler::WorkStealing => Some(index % self.locals),
+            Scheduler::Shared => self.worker_node(index),
+        }
+    }
+
+    /// NUMA node of worker `index`, see `TPBuilder::numa`.
+    #[cfg(feature = "topology")]
+    pub(crate) fn worker_node(&self, index: usize) -> Option<usize> {
+        let numa = self.numa.as_ref()?;
+
+        Some(index % numa.nodes.len())
+    }
+
+    #[cfg(not(feature = "topology"))]
+    pub(crate) fn worker_node(&self, _: usize) -> Option<usize> {
+        None
+    }
+
+    /// The core size plus the headroom `max_size` gave above the built size,
+    /// so `ThreadPool::resize` moves both.
+    pub fn worker_limit(&self) -> usize {
+        let size = self.size();
+
+        self.config
+            .max_size
+            .map_or(size, |max| size + max.saturating_sub(self.config.size))
+    }
+
+    pub fn decrement_worker_count(&self) {
+        let state = self.state.fetch_dec_worker_count();
+
+        if state.worker_count() == 1 && state.is_shutdown() {
+            self.finalize_instance();
+        }
+    }
+
+    pub fn disconnected(&self) {
+        if self.state.try_transition_to_shutdown() {
+            self.wake_workers();
+
+            if let Some(f) = self.config.on_disconnect.as_ref() {
+                f();
+            }
+
+            self.try_finalize();
+        }
+    }
+
+    pub fn close(&self) {
+        if self.state.try_transition_to_shutdown() {
+            self.wake_workers();
+            self.try_finalize();
+        }
+    }
+
+    pub fn close_force<T>(&self, drain: impl FnOnce() -> Vec<Task<T>>) -> Vec<Task<T>> {
+        let mut abandoned = Vec::new();
+
+        if self.state.try_transition_to_stop() {
+            self.wake_workers();
+
+            abandoned = drain();
+
+            self.try_finalize();
+        }
+
+        for child in self.children() {
+            child.close_force();
+        }
+
+        abandoned
+    }
+
+    pub fn await_termination_until(&self, deadline: Option<Instant>) -> bool {
+        let mut lock = self.termination_mutex.lock().unwrap();
+
+        while !self.state.load().is_terminated() {
+            lock = match deadline {
+                None => self.termination_signal.wait(lock).unwrap(),
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(timeout) => {
+                        self.termination_signal
+                            .wait_timeout(lock, timeout)
+                            .unwrap()
+                            .0
+                    }
+                    None => return false,
+                },
+            };
+        }
+        drop(lock);
+
+        self.children()
+            .iter()
+            .all(|child| child.await_termination_until(deadline))
+    }
+
+    pub fn wake_workers(&self) {
+        self.shutdown_tx.lock().unwrap().take();
+        self.control.notify_all();
+    }
+
+    pub fn try_finalize(&self) {
+        let state = self.state.load();
+
+        if state.is_shutdown() && state.worker_count() == 0 {
+            self.finalize_instance();
+        }
     }
 
     pub fn finalize_instance(&self) {
         if self.state.try_transition_to_tidying() {
             self.state.transition_to_terminated();
 
-            self.termination_signal.notify_all();
+            {
+                let _lock = self.termination_mutex.lock().unwrap();
+                self.termination_signal.notify_all();
+            }
+
+            for child in self.children() {
+                child.close();
+            }
         }
     }
+
+    fn children(&self) -> Vec<Arc<dyn Child>> {
+        self.children.lock().unwrap().clone()
+    }
 }
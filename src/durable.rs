@@ -0,0 +1,347 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::{core::ThreadPool, job};
+use job::{Job, JobBox};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Storage for jobs that must survive a crash, see `DurableQueue`.
+///
+/// A job is pushed before it is handed to the pool and acknowledged once it
+/// has run. Whatever was pushed but not acknowledged is pending and is
+/// handed back by `pending`, e.g. after a restart.
+pub trait JobQueue<T>: Send + Sync {
+    /// Stores `job` and returns the id it is acknowledged with.
+    fn push(&self, job: &T) -> io::Result<u64>;
+
+    fn ack(&self, id: u64) -> io::Result<()>;
+
+    /// Jobs pushed but not acknowledged, oldest first.
+    fn pending(&self) -> io::Result<Vec<(u64, T)>>;
+
+    /// Number of pending jobs.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// `JobQueue` kept in an append-only file of JSON lines, synced on every
+/// push and ack.
+pub struct FileQueue<T> {
+    path: PathBuf,
+    log: Mutex<Log>,
+    _marker: PhantomData<fn(T)>,
+}
+
+struct Log {
+    file: File,
+    next_id: u64,
+    pending: BTreeMap<u64, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Record<T> {
+    Push { id: u64, job: T },
+    Ack { id: u64 },
+}
+
+impl<T: Serialize + DeserializeOwned> FileQueue<T> {
+    /// Opens the log at `path`, loading the jobs it still has pending.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<FileQueue<T>> {
+        let path = path.as_ref().to_path_buf();
+        let mut pending = BTreeMap::new();
+        let mut next_id = 0;
+
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).lines() {
+                let line = line?;
+
+                if line.is_empty() {
+                    continue;
+                }
+
+                match serde_json::from_str::<Record<T>>(&line).map_err(invalid_data)? {
+                    Record::Push { id, .. } => {
+                        pending.insert(id, line);
+                        next_id = next_id.max(id + 1);
+                    }
+                    Record::Ack { id } => {
+                        pending.remove(&id);
+                    }
+                }
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(FileQueue {
+            path,
+            log: Mutex::new(Log {
+                file,
+                next_id,
+                pending,
+            }),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Rewrites the log down to the pending jobs.
+    pub fn compact(&self) -> io::Result<()> {
+        let mut log = self.log.lock().unwrap();
+        let tmp = self.path.with_extension("compact");
+
+        {
+            let mut file = File::create(&tmp)?;
+
+            for line in log.pending.values() {
+                writeln!(file, "{}", line)?;
+            }
+
+            file.sync_all()?;
+        }
+
+        fs::rename(&tmp, &self.path)?;
+        log.file = OpenOptions::new().append(true).open(&self.path)?;
+
+        Ok(())
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> JobQueue<T> for FileQueue<T> {
+    fn push(&self, job: &T) -> io::Result<u64> {
+        let mut log = self.log.lock().unwrap();
+        let id = log.next_id;
+        let line = serde_json::to_string(&Record::Push { id, job }).map_err(invalid_data)?;
+
+        log.append(&line)?;
+        log.pending.insert(id, line);
+        log.next_id += 1;
+
+        Ok(id)
+    }
+
+    fn ack(&self, id: u64) -> io::Result<()> {
+        let mut log = self.log.lock().unwrap();
+
+        if log.pending.remove(&id).is_some() {
+            let line = serde_json::to_string(&Record::<()>::Ack { id }).map_err(invalid_data)?;
+            log.append(&line)?;
+        }
+
+        Ok(())
+    }
+
+    fn pending(&self) -> io::Result<Vec<(u64, T)>> {
+        let log = self.log.lock().unwrap();
+
+        log.pending
+            .values()
+            .map(
+                |line| match serde_json::from_str(line).map_err(invalid_data)? {
+                    Record::Push { id, job } => Ok((id, job)),
+                    Record::Ack { .. } => unreachable!(),
+                },
+            )
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        self.log.lock().unwrap().pending.len()
+    }
+}
+
+impl Log {
+    fn append(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.file, "{}", line)?;
+        self.file.sync_data()
+    }
+}
+
+/// Sends jobs to a pool through a `JobQueue`, so pending work survives
+/// process crashes.
+///
+/// Every job is pushed to the queue before it is handed to the pool and
+/// acknowledged once it has run (or panicked). Jobs that were pushed but
+/// never acknowledged, e.g. because the process crashed or `close_force`
+/// discarded them, are resubmitted by `resume`. Delivery is therefore
+/// at-least-once: a job that was running when the process died will run
+/// again.
+pub struct DurableQueue<T, Q = FileQueue<T>> {
+    queue: Arc<Q>,
+    _marker: PhantomData<fn(T)>,
+}
+
+/// A job sent through a `DurableQueue`, acknowledged once it has run.
+/// Pools take it as is, as `ThreadPool<Durable<T>>`, or boxed.
+pub struct Durable<T, Q = FileQueue<T>> {
+    job: T,
+    ack: Ack<Q>,
+}
+
+impl<T> DurableQueue<T>
+where
+    T: Job + Serialize + DeserializeOwned,
+{
+    /// Opens a `FileQueue` at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<DurableQueue<T>> {
+        Ok(DurableQueue::with_queue(FileQueue::open(path)?))
+    }
+
+    /// See `FileQueue::compact`.
+    pub fn compact(&self) -> io::Result<()> {
+        self.queue.compact()
+    }
+}
+
+impl<T, Q> DurableQueue<T, Q>
+where
+    T: Job,
+    Q: JobQueue<T> + 'static,
+{
+    pub fn with_queue(queue: Q) -> DurableQueue<T, Q> {
+        DurableQueue {
+            queue: Arc::new(queue),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn queue(&self) -> &Q {
+        &self.queue
+    }
+
+    /// Number of jobs not acknowledged yet.
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn send<J>(&self, pool: &ThreadPool<J>, job: T) -> io::Result<()>
+    where
+        J: Job + From<Durable<T, Q>>,
+    {
+        let id = self.queue.push(&job)?;
+
+        self.submit(pool, id, job)
+    }
+
+    /// Resubmits every pending job and returns how many there were.
+    pub fn resume<J>(&self, pool: &ThreadPool<J>) -> io::Result<usize>
+    where
+        J: Job + From<Durable<T, Q>>,
+    {
+        let pending = self.queue.pending()?;
+        let count = pending.len();
+
+        for (id, job) in pending {
+            self.submit(pool, id, job)?;
+        }
+
+        Ok(count)
+    }
+
+    fn submit<J>(&self, pool: &ThreadPool<J>, id: u64, job: T) -> io::Result<()>
+    where
+        J: Job + From<Durable<T, Q>>,
+    {
+        let ack = Ack {
+            queue: self.queue.clone(),
+            id,
+        };
+
+        pool.send(J::from(Durable { job, ack }))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "thread pool is shut down"))
+    }
+}
+
+impl<T, Q> Clone for DurableQueue<T, Q> {
+    fn clone(&self) -> Self {
+        DurableQueue {
+            queue: self.queue.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, Q> Job for Durable<T, Q>
+where
+    T: Job,
+    Q: JobQueue<T> + 'static,
+{
+    // A panicking job is acknowledged as well, it would only panic again.
+    fn call(self) {
+        let Durable { job, ack } = self;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| job.call()));
+
+        ack.acknowledge();
+        if let Err(payload) = result {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+impl<T, Q> From<Durable<T, Q>> for Box<dyn JobBox>
+where
+    T: Job,
+    Q: JobQueue<T> + 'static,
+{
+    fn from(job: Durable<T, Q>) -> Box<dyn JobBox> {
+        Box::new(job)
+    }
+}
+
+struct Ack<Q> {
+    queue: Arc<Q>,
+    id: u64,
+}
+
+impl<Q> Ack<Q> {
+    // A failed ack only means the job runs again after a restart.
+    fn acknowledge<T>(&self)
+    where
+        Q: JobQueue<T>,
+    {
+        let _ = self.queue.ack(self.id);
+    }
+}
+
+pub fn write_jobs<T, W>(mut writer: W, jobs: &[T]) -> io::Result<()>
+where
+    T: Serialize,
+    W: Write,
+{
+    for job in jobs {
+        serde_json::to_writer(&mut writer, job).map_err(invalid_data)?;
+        writer.write_all(b"\n")?;
+    }
+
+    writer.flush()
+}
+
+pub fn read_jobs<T, R>(reader: R) -> io::Result<Vec<T>>
+where
+    T: DeserializeOwned,
+    R: Read,
+{
+    let mut jobs = Vec::new();
+
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+
+        if !line.is_empty() {
+            jobs.push(serde_json::from_str(&line).map_err(invalid_data)?);
+        }
+    }
+
+    Ok(jobs)
+}
+
+fn invalid_data(err: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
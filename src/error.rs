@@ -0,0 +1,327 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+
+/// Smallest stack a worker can be given. Windows reserves stacks in 64 KiB
+/// chunks; elsewhere this is the common `PTHREAD_STACK_MIN`.
+pub const MIN_STACK_SIZE: usize = if cfg!(windows) { 64 * 1024 } else { 16 * 1024 };
+
+/// Worker stacks are rounded up to a multiple of this.
+pub const STACK_GRANULARITY: usize = if cfg!(windows) {
+    64 * 1024
+} else if cfg!(all(target_vendor = "apple", target_arch = "aarch64")) {
+    16 * 1024
+} else {
+    4 * 1024
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ConfigError {
+    ZeroSize,
+    StackTooSmall {
+        requested: usize,
+        minimum: usize,
+    },
+    StackTooLarge {
+        requested: usize,
+    },
+    MaxBelowCore {
+        core: usize,
+        max: usize,
+    },
+    NoCpus,
+    /// The jobs given to `TPBuilder::restore_from` are not of the pool's job
+    /// type.
+    RestoredJobType,
+    /// The jobs sent while building, such as those given to
+    /// `TPBuilder::restore_from`, do not fit in the queue.
+    QueueSmallerThanBurst {
+        queue: usize,
+        burst: usize,
+    },
+    /// The pool refused a job given to `TPBuilder::restore_from`. The builder
+    /// keeps the jobs that did not start for the next build.
+    RestoreRefused,
+    /// Two settings that cannot work together, named as in `TPBuilder`.
+    ConflictingPolicies(&'static str, &'static str),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::ZeroSize => fmt.write_str("at least one thread required"),
+            ConfigError::StackTooSmall { requested, minimum } => write!(
+                fmt,
+                "stack size of {} bytes is below the minimum of {} bytes",
+                requested, minimum
+            ),
+            ConfigError::StackTooLarge { requested } => {
+                write!(fmt, "stack size of {} bytes cannot be allocated", requested)
+            }
+            ConfigError::MaxBelowCore { core, max } => write!(
+                fmt,
+                "max size of {} is below the core size of {}",
+                max, core
+            ),
+            ConfigError::NoCpus => fmt.write_str("no CPUs to pin workers to"),
+            ConfigError::RestoredJobType => {
+                fmt.write_str("restored jobs do not match the pool job type")
+            }
+            ConfigError::RestoreRefused => fmt.write_str("the pool refused a restored job"),
+            ConfigError::QueueSmallerThanBurst { queue, burst } => write!(
+                fmt,
+                "queue of {} cannot hold the {} jobs sent while building",
+                queue, burst
+            ),
+            ConfigError::ConflictingPolicies(a, b) => write!(fmt, "{} conflicts with {}", a, b),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum JobError {
+    Panicked(String),
+    Cancelled,
+    /// The result was already taken, e.g. by `JobHandle::try_join`.
+    Taken,
+}
+
+impl JobError {
+    pub fn from_panic(payload: &(dyn Any + Send)) -> JobError {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<dyn Any>".to_string()
+        };
+
+        JobError::Panicked(message)
+    }
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JobError::Panicked(ref message) => write!(fmt, "job panicked: {}", message),
+            JobError::Cancelled => fmt.write_str("job was dropped before it ran"),
+            JobError::Taken => fmt.write_str("job result was already taken"),
+        }
+    }
+}
+
+impl Error for JobError {}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Elapsed;
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("deadline has elapsed")
+    }
+}
+
+impl Error for Elapsed {}
+
+/// Error returned by `ThreadPool::reserve_group` when too few workers are
+/// left unreserved. One worker is always kept for untagged jobs.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct GroupTooLarge {
+    pub requested: usize,
+    pub available: usize,
+}
+
+impl fmt::Display for GroupTooLarge {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "cannot reserve {} workers, only {} can be reserved",
+            self.requested, self.available
+        )
+    }
+}
+
+impl Error for GroupTooLarge {}
+
+/// Error returned by the pool's send methods with the job that was not
+/// queued.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SubmitError<T> {
+    /// The shed policy dropped the job.
+    Shed(T),
+    /// The pool was closed before the job could be queued.
+    ShuttingDown(T),
+    /// The queue had no room and the job could not wait for it: it was sent
+    /// with `try_send` or the rejection policy is `Abort`.
+    Full(T),
+    /// The pool's `Allocator` refused the job's box.
+    AllocRefused(T),
+    /// `send_timeout` gave up waiting for room.
+    Timeout(T),
+    /// `send_to_group` named a group that was never reserved.
+    UnknownGroup(T),
+}
+
+impl<T> SubmitError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            SubmitError::Shed(job)
+            | SubmitError::ShuttingDown(job)
+            | SubmitError::Full(job)
+            | SubmitError::AllocRefused(job)
+            | SubmitError::Timeout(job)
+            | SubmitError::UnknownGroup(job) => job,
+        }
+    }
+
+    pub(crate) fn map<U, F: FnOnce(T) -> U>(self, f: F) -> SubmitError<U> {
+        match self {
+            SubmitError::Shed(job) => SubmitError::Shed(f(job)),
+            SubmitError::ShuttingDown(job) => SubmitError::ShuttingDown(f(job)),
+            SubmitError::Full(job) => SubmitError::Full(f(job)),
+            SubmitError::AllocRefused(job) => SubmitError::AllocRefused(f(job)),
+            SubmitError::Timeout(job) => SubmitError::Timeout(f(job)),
+            SubmitError::UnknownGroup(job) => SubmitError::UnknownGroup(f(job)),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        matches!(*self, SubmitError::Full(_))
+    }
+
+    pub fn is_alloc_refused(&self) -> bool {
+        matches!(*self, SubmitError::AllocRefused(_))
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(*self, SubmitError::Timeout(_))
+    }
+
+    pub fn is_shed(&self) -> bool {
+        matches!(*self, SubmitError::Shed(_))
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        matches!(*self, SubmitError::ShuttingDown(_))
+    }
+}
+
+impl<T> fmt::Debug for SubmitError<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SubmitError::Shed(_) => fmt.write_str("Shed(..)"),
+            SubmitError::ShuttingDown(_) => fmt.write_str("ShuttingDown(..)"),
+            SubmitError::Full(_) => fmt.write_str("Full(..)"),
+            SubmitError::AllocRefused(_) => fmt.write_str("AllocRefused(..)"),
+            SubmitError::Timeout(_) => fmt.write_str("Timeout(..)"),
+            SubmitError::UnknownGroup(_) => fmt.write_str("UnknownGroup(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for SubmitError<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SubmitError::Shed(_) => fmt.write_str("job shed under load"),
+            SubmitError::ShuttingDown(_) => fmt.write_str("pool is shutting down"),
+            SubmitError::Full(_) => fmt.write_str("queue is full"),
+            SubmitError::AllocRefused(_) => fmt.write_str("allocator refused the job"),
+            SubmitError::Timeout(_) => fmt.write_str("timed out waiting for room in the queue"),
+            SubmitError::UnknownGroup(_) => fmt.write_str("no such worker group"),
+        }
+    }
+}
+
+impl<T: Send> Error for SubmitError<T> {}
+
+/// Returned by `RtProducer::send_from_rt` with the job that was not queued.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RtReject<T> {
+    /// The producer's ring is full.
+    Full(T),
+    /// The pool no longer accepts jobs.
+    Closed(T),
+}
+
+impl<T> RtReject<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            RtReject::Full(job) | RtReject::Closed(job) => job,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        matches!(*self, RtReject::Full(_))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(*self, RtReject::Closed(_))
+    }
+}
+
+impl<T> fmt::Debug for RtReject<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RtReject::Full(_) => fmt.write_str("Full(..)"),
+            RtReject::Closed(_) => fmt.write_str("Closed(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for RtReject<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RtReject::Full(_) => fmt.write_str("realtime ring is full"),
+            RtReject::Closed(_) => fmt.write_str("pool is shutting down"),
+        }
+    }
+}
+
+impl<T: Send> Error for RtReject<T> {}
+
+/// Returned by `Router::send` with the job that was not queued.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RouteError<T> {
+    /// The routing function picked a key with no pool.
+    Unrouted(T),
+    /// The pool the job was routed to no longer accepts jobs.
+    Closed(T),
+}
+
+impl<T> RouteError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            RouteError::Unrouted(job) | RouteError::Closed(job) => job,
+        }
+    }
+
+    pub fn is_unrouted(&self) -> bool {
+        matches!(*self, RouteError::Unrouted(_))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(*self, RouteError::Closed(_))
+    }
+}
+
+impl<T> fmt::Debug for RouteError<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RouteError::Unrouted(_) => fmt.write_str("Unrouted(..)"),
+            RouteError::Closed(_) => fmt.write_str("Closed(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for RouteError<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RouteError::Unrouted(_) => fmt.write_str("no pool for the job's route"),
+            RouteError::Closed(_) => fmt.write_str("pool is shutting down"),
+        }
+    }
+}
+
+impl<T: Send> Error for RouteError<T> {}
@@ -0,0 +1,100 @@
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use futures::future::FutureObj;
+use futures::task::{Spawn, SpawnError};
+
+use crate::core::ThreadPool;
+use crate::error::JobError;
+use crate::handle::{self, Completer, JobHandle};
+use crate::job::JobBox;
+
+type BoxFuture<R> = Pin<Box<dyn Future<Output = R> + Send>>;
+
+// A spawned future. Each wakeup sends a job polling it once; the slot is
+// emptied when the future completes, so late wakeups do nothing.
+struct Task<R> {
+    future: Mutex<Option<(BoxFuture<R>, Completer<R>)>>,
+    pool: ThreadPool<Box<dyn JobBox>>,
+}
+
+impl<R: Send + 'static> Wake for Task<R> {
+    fn wake(self: Arc<Self>) {
+        let pool = self.pool.clone();
+
+        // Once the pool is closed the future is dropped along with the job
+        // and its handle resolves to `JobError::Cancelled`.
+        let _ = pool.send_wakeup(Box::new(move || self.poll()));
+    }
+}
+
+impl<R: Send + 'static> Task<R> {
+    fn poll(self: Arc<Self>) {
+        let mut slot = self.future.lock().unwrap();
+
+        let cancelled = match &*slot {
+            Some((_, completer)) => completer.is_cancelled(),
+            None => return,
+        };
+
+        if cancelled {
+            slot.take();
+            return;
+        }
+
+        let waker = Waker::from(self.clone());
+        let mut cx = Context::from_waker(&waker);
+        let (future, _) = slot.as_mut().unwrap();
+
+        match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(&mut cx))) {
+            Ok(Poll::Pending) => {}
+            Ok(Poll::Ready(value)) => {
+                let (_, completer) = slot.take().unwrap();
+                completer.complete(Ok(value));
+            }
+            Err(payload) => {
+                let (_, completer) = slot.take().unwrap();
+                completer.complete(Err(JobError::from_panic(&*payload)));
+                drop(slot);
+                panic::resume_unwind(payload);
+            }
+        }
+    }
+}
+
+pub fn spawn<F>(pool: &ThreadPool<Box<dyn JobBox>>, future: F) -> JobHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let (completer, handle) = handle::pair();
+    let task = Arc::new(Task {
+        future: Mutex::new(Some((Box::pin(future) as BoxFuture<_>, completer))),
+        pool: pool.clone(),
+    });
+
+    task.wake();
+
+    handle
+}
+
+// `SpawnExt` comes with this through its blanket impl.
+impl Spawn for ThreadPool<Box<dyn JobBox>> {
+    fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), SpawnError> {
+        Spawn::status(self)?;
+        spawn(self, future).detach();
+
+        Ok(())
+    }
+
+    fn status(&self) -> Result<(), SpawnError> {
+        if self.is_terminating() || self.is_terminated() {
+            return Err(SpawnError::shutdown());
+        }
+
+        Ok(())
+    }
+}
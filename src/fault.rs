@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::rng;
+
+/// Faults a pool built with `TPBuilder::fault_injection` injects at random,
+/// to exercise the retry and fallback paths of code built on it. Rates are
+/// fractions from 0.0 (never) to 1.0 (always); the same seed injects the same
+/// sequence of faults.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct FaultPolicy {
+    pub seed: u64,
+    /// Fraction of dequeued jobs held back for `delay` before they run.
+    pub delay_rate: f64,
+    pub delay: Duration,
+    /// Fraction of submissions refused as if the pool were closed.
+    pub send_failure_rate: f64,
+    /// Fraction of jobs that panic instead of running.
+    pub panic_rate: f64,
+}
+
+impl FaultPolicy {
+    pub fn new(seed: u64) -> FaultPolicy {
+        FaultPolicy {
+            seed,
+            ..FaultPolicy::default()
+        }
+    }
+
+    pub fn delay_dequeues(mut self, rate: f64, delay: Duration) -> Self {
+        self.delay_rate = rate;
+        self.delay = delay;
+        self
+    }
+
+    pub fn fail_sends(mut self, rate: f64) -> Self {
+        self.send_failure_rate = rate;
+        self
+    }
+
+    pub fn panic_jobs(mut self, rate: f64) -> Self {
+        self.panic_rate = rate;
+        self
+    }
+}
+
+pub struct Injector {
+    policy: FaultPolicy,
+    seq: AtomicU64,
+}
+
+impl Injector {
+    pub fn new(policy: FaultPolicy) -> Injector {
+        Injector {
+            policy,
+            seq: AtomicU64::new(policy.seed),
+        }
+    }
+
+    fn roll(&self, rate: f64) -> bool {
+        if rate <= 0.0 {
+            return false;
+        }
+
+        let seq = self.seq.fetch_add(rng::GAMMA, Ordering::Relaxed);
+
+        rng::unit(rng::mix(seq.wrapping_add(rng::GAMMA))) < rate
+    }
+
+    pub fn delay(&self) -> Option<Duration> {
+        if self.roll(self.policy.delay_rate) {
+            Some(self.policy.delay)
+        } else {
+            None
+        }
+    }
+
+    pub fn fail_send(&self) -> bool {
+        self.roll(self.policy.send_failure_rate)
+    }
+
+    pub fn panic_job(&self) -> bool {
+        self.roll(self.policy.panic_rate)
+    }
+}
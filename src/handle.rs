@@ -0,0 +1,323 @@
+use std::fmt;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use crate::error::JobError;
+
+/// Handle to the result of a job running on a pool.
+///
+/// The result can be waited for with `join` or by awaiting the handle from any
+/// async runtime. If the job is dropped without running, e.g. because the pool
+/// was force-closed, the handle resolves to `JobError::Cancelled`.
+///
+/// Dropping the handle detaches the job by default; see `on_drop`.
+pub struct JobHandle<R> {
+    shared: Arc<Shared<R>>,
+    on_drop: OnDrop,
+}
+
+/// What happens to a job when its handle is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnDrop {
+    /// The job keeps running and its result is discarded.
+    Detach,
+    /// The job is skipped if it has not started yet.
+    Cancel,
+}
+
+struct Shared<R> {
+    slot: Mutex<Slot<R>>,
+    ready: Condvar,
+}
+
+struct Slot<R> {
+    result: Option<Result<R, JobError>>,
+    waker: Option<Waker>,
+    cancelled: bool,
+    // The result was handed out; later takes get `JobError::Taken`.
+    taken: bool,
+}
+
+impl<R> Slot<R> {
+    fn is_finished(&self) -> bool {
+        self.result.is_some() || self.taken
+    }
+
+    fn take(&mut self) -> Option<Result<R, JobError>> {
+        if self.taken {
+            return Some(Err(JobError::Taken));
+        }
+
+        let result = self.result.take();
+        self.taken = result.is_some();
+
+        result
+    }
+}
+
+/// Resolves a `JobHandle`. Dropping it unresolved reports
+/// `JobError::Cancelled`.
+pub struct Completer<R> {
+    shared: Option<Arc<Shared<R>>>,
+}
+
+pub fn pair<R>() -> (Completer<R>, JobHandle<R>) {
+    let shared = Arc::new(Shared {
+        slot: Mutex::new(Slot {
+            result: None,
+            waker: None,
+            cancelled: false,
+            taken: false,
+        }),
+        ready: Condvar::new(),
+    });
+    let completer = Completer {
+        shared: Some(shared.clone()),
+    };
+    let handle = JobHandle {
+        shared,
+        on_drop: OnDrop::Detach,
+    };
+
+    (completer, handle)
+}
+
+pub fn wrap<F, R>(f: F) -> (impl FnOnce() + Send + 'static, JobHandle<R>)
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (completer, handle) = pair();
+
+    let job = move || {
+        if completer.is_cancelled() {
+            return;
+        }
+
+        match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(value) => completer.complete(Ok(value)),
+            Err(payload) => {
+                completer.complete(Err(JobError::from_panic(&*payload)));
+                panic::resume_unwind(payload);
+            }
+        }
+    };
+
+    (job, handle)
+}
+
+impl<R> JobHandle<R> {
+    pub fn is_finished(&self) -> bool {
+        self.shared.slot.lock().unwrap().is_finished()
+    }
+
+    pub fn on_drop(mut self, on_drop: OnDrop) -> Self {
+        self.on_drop = on_drop;
+        self
+    }
+
+    /// Lets the job run to completion without waiting for its result.
+    pub fn detach(mut self) {
+        self.on_drop = OnDrop::Detach;
+    }
+
+    /// Takes the result if the job has finished. Once taken, every way of
+    /// getting the result returns `JobError::Taken`.
+    pub fn try_join(&self) -> Option<Result<R, JobError>> {
+        self.shared.slot.lock().unwrap().take()
+    }
+
+    /// Waits up to `timeout` for the result; `None` if the job is still running.
+    pub fn join_timeout(&self, timeout: Duration) -> Option<Result<R, JobError>> {
+        let slot = self.shared.slot.lock().unwrap();
+        let (mut slot, _) = self
+            .shared
+            .ready
+            .wait_timeout_while(slot, timeout, |slot| !slot.is_finished())
+            .unwrap();
+
+        slot.take()
+    }
+
+    pub fn join(self) -> Result<R, JobError> {
+        let mut slot = self.shared.slot.lock().unwrap();
+
+        loop {
+            if let Some(result) = slot.take() {
+                return result;
+            }
+
+            slot = self.shared.ready.wait(slot).unwrap();
+        }
+    }
+}
+
+impl<R> JobHandle<R> {
+    fn poll_result(&self, waker: &Waker) -> Option<Result<R, JobError>> {
+        let mut slot = self.shared.slot.lock().unwrap();
+
+        match slot.take() {
+            Some(result) => Some(result),
+            None => {
+                slot.waker = Some(waker.clone());
+                None
+            }
+        }
+    }
+}
+
+impl<R> Future for JobHandle<R> {
+    type Output = Result<R, JobError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.poll_result(cx.waker()) {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
+}
+
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Blocks until the first of `handles` finishes and returns its index and
+/// result. The other handles are dropped, so jobs whose handle was set to
+/// `OnDrop::Cancel` are skipped if they have not started yet.
+///
+/// Panics if `handles` is empty.
+pub fn select<R, I>(handles: I) -> (usize, Result<R, JobError>)
+where
+    I: IntoIterator<Item = JobHandle<R>>,
+{
+    let handles: Vec<_> = handles.into_iter().collect();
+    assert!(!handles.is_empty(), "select called without handles");
+
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+
+    loop {
+        for (index, handle) in handles.iter().enumerate() {
+            if let Some(result) = handle.poll_result(&waker) {
+                return (index, result);
+            }
+        }
+
+        thread::park();
+    }
+}
+
+/// Waits for every handle and returns their results in order.
+pub fn join_all<R, I>(handles: I) -> Vec<Result<R, JobError>>
+where
+    I: IntoIterator<Item = JobHandle<R>>,
+{
+    wait_all(handles.into_iter().collect(), None)
+        .into_iter()
+        .map(Option::unwrap)
+        .collect()
+}
+
+/// Like `join_all`, but gives up after `timeout`. Jobs that have not finished
+/// by then are reported as `None` and their handles are dropped.
+pub fn join_all_timeout<R, I>(handles: I, timeout: Duration) -> Vec<Option<Result<R, JobError>>>
+where
+    I: IntoIterator<Item = JobHandle<R>>,
+{
+    wait_all(
+        handles.into_iter().collect(),
+        Some(Instant::now() + timeout),
+    )
+}
+
+fn wait_all<R>(
+    handles: Vec<JobHandle<R>>,
+    deadline: Option<Instant>,
+) -> Vec<Option<Result<R, JobError>>> {
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut results: Vec<_> = handles.iter().map(|_| None).collect();
+    let mut left = handles.len();
+
+    loop {
+        for (handle, result) in handles.iter().zip(results.iter_mut()) {
+            if result.is_none() {
+                *result = handle.poll_result(&waker);
+
+                if result.is_some() {
+                    left -= 1;
+                }
+            }
+        }
+
+        match deadline {
+            _ if left == 0 => return results,
+            None => thread::park(),
+            Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                Some(timeout) => thread::park_timeout(timeout),
+                None => return results,
+            },
+        }
+    }
+}
+
+impl<R> fmt::Debug for JobHandle<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("JobHandle")
+            .field("finished", &self.is_finished())
+            .field("on_drop", &self.on_drop)
+            .finish()
+    }
+}
+
+impl<R> Drop for JobHandle<R> {
+    fn drop(&mut self) {
+        if self.on_drop == OnDrop::Cancel {
+            self.shared.slot.lock().unwrap().cancelled = true;
+        }
+    }
+}
+
+impl<R> Completer<R> {
+    /// Whether the handle was dropped with `OnDrop::Cancel`.
+    pub fn is_cancelled(&self) -> bool {
+        match self.shared {
+            Some(ref shared) => shared.slot.lock().unwrap().cancelled,
+            None => true,
+        }
+    }
+
+    pub fn complete(mut self, result: Result<R, JobError>) {
+        self.set(result);
+    }
+
+    fn set(&mut self, result: Result<R, JobError>) {
+        if let Some(shared) = self.shared.take() {
+            let waker = {
+                let mut slot = shared.slot.lock().unwrap();
+
+                slot.result = Some(result);
+                slot.waker.take()
+            };
+
+            shared.ready.notify_all();
+
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<R> Drop for Completer<R> {
+    fn drop(&mut self) {
+        self.set(Err(JobError::Cancelled));
+    }
+}
@@ -0,0 +1,76 @@
+use std::hint;
+use std::thread;
+use std::time::Duration;
+
+/// What an idle worker does next after finding the queue empty.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Idle {
+    /// Poll the queue again.
+    Retry,
+    /// Block until a job arrives or the worker is woken.
+    Park,
+}
+
+/// Decides how an idle worker waits for work, trading wakeup latency for CPU
+/// time. `idle` is called after every empty poll with the number of empty
+/// polls so far and may spin, yield or sleep before returning.
+pub trait IdleStrategy: Send + Sync {
+    fn idle(&self, attempt: u32) -> Idle;
+}
+
+/// Blocks as soon as the queue is empty. This is the default.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ParkImmediately;
+
+impl IdleStrategy for ParkImmediately {
+    fn idle(&self, _: u32) -> Idle {
+        Idle::Park
+    }
+}
+
+/// Spins `spins` times before blocking.
+#[derive(Debug, Copy, Clone)]
+pub struct SpinThenPark {
+    pub spins: u32,
+}
+
+impl IdleStrategy for SpinThenPark {
+    fn idle(&self, attempt: u32) -> Idle {
+        if attempt < self.spins {
+            hint::spin_loop();
+            Idle::Retry
+        } else {
+            Idle::Park
+        }
+    }
+}
+
+/// Never blocks; sleeps between polls, doubling from `min` up to `max`.
+#[derive(Debug, Copy, Clone)]
+pub struct SleepBackoff {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl IdleStrategy for SleepBackoff {
+    fn idle(&self, attempt: u32) -> Idle {
+        let sleep = self
+            .min
+            .checked_mul(1 << attempt.min(31))
+            .map_or(self.max, |sleep| sleep.min(self.max));
+
+        thread::sleep(sleep);
+        Idle::Retry
+    }
+}
+
+/// Never blocks nor sleeps, keeping a core busy for the lowest latency.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BusySpin;
+
+impl IdleStrategy for BusySpin {
+    fn idle(&self, _: u32) -> Idle {
+        hint::spin_loop();
+        Idle::Retry
+    }
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use metrics::{counter, gauge, histogram};
+
+use crate::core::Inner;
+
+// Pools without a name are reported under this label.
+const UNNAMED: &str = "multix";
+
+fn pool(inner: &Inner) -> String {
+    inner.config.name.as_deref().unwrap_or(UNNAMED).to_string()
+}
+
+pub fn started(inner: &Inner, queued: usize, busy: usize, wait: Duration) {
+    let pool = pool(inner);
+
+    gauge!("multix_queue_depth", "pool" => pool.clone()).set(queued as f64);
+    gauge!("multix_busy_workers", "pool" => pool.clone()).set(busy as f64);
+    histogram!("multix_job_wait_seconds", "pool" => pool).record(wait.as_secs_f64());
+}
+
+pub fn finished(inner: &Inner, busy: usize, run: Duration, panicked: bool) {
+    let pool = pool(inner);
+
+    gauge!("multix_busy_workers", "pool" => pool.clone()).set(busy as f64);
+    histogram!("multix_job_run_seconds", "pool" => pool.clone()).record(run.as_secs_f64());
+    if panicked {
+        counter!("multix_jobs_panicked_total", "pool" => pool).increment(1);
+    } else {
+        counter!("multix_jobs_completed_total", "pool" => pool).increment(1);
+    }
+}
@@ -0,0 +1,148 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::marker::PhantomData;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::{core::ThreadPool, job::Job};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Accepts jobs from other processes over a Unix domain socket.
+///
+/// Each line received on a connection is a JSON-encoded job. The server
+/// answers every line with `ok` once the job is queued, or `err <reason>`.
+pub struct IpcServer {
+    path: PathBuf,
+    closed: Arc<AtomicBool>,
+    acceptor: Option<JoinHandle<()>>,
+}
+
+pub struct IpcClient<T> {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl IpcServer {
+    pub fn bind<T, P>(path: P, pool: ThreadPool<T>) -> io::Result<IpcServer>
+    where
+        T: Job + DeserializeOwned,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&path)?;
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let acceptor = {
+            let closed = closed.clone();
+
+            thread::Builder::new()
+                .name("multix-ipc".to_string())
+                .spawn(move || {
+                    for stream in listener.incoming() {
+                        if closed.load(Ordering::SeqCst) {
+                            break;
+                        }
+
+                        if let Ok(stream) = stream {
+                            let pool = pool.clone();
+                            let _ = thread::Builder::new()
+                                .name("multix-ipc-conn".to_string())
+                                .spawn(move || serve(stream, pool));
+                        }
+                    }
+                })?
+        };
+
+        Ok(IpcServer {
+            path,
+            closed,
+            acceptor: Some(acceptor),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn close(&mut self) {
+        if let Some(acceptor) = self.acceptor.take() {
+            self.closed.store(true, Ordering::SeqCst);
+
+            let _ = UnixStream::connect(&self.path);
+            let _ = acceptor.join();
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn serve<T>(stream: UnixStream, pool: ThreadPool<T>)
+where
+    T: Job + DeserializeOwned,
+{
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+
+        let reply = match serde_json::from_str::<T>(&line) {
+            Ok(job) => match pool.send(job) {
+                Ok(_) => "ok".to_string(),
+                Err(_) => "err thread pool is shut down".to_string(),
+            },
+            Err(err) => format!("err {}", err),
+        };
+
+        if writeln!(writer, "{}", reply).is_err() {
+            return;
+        }
+    }
+}
+
+impl<T: Serialize> IpcClient<T> {
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<IpcClient<T>> {
+        let writer = UnixStream::connect(path)?;
+        let reader = BufReader::new(writer.try_clone()?);
+
+        Ok(IpcClient {
+            reader,
+            writer,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn send(&mut self, job: &T) -> io::Result<()> {
+        let line = serde_json::to_string(job)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        writeln!(self.writer, "{}", line)?;
+
+        let mut reply = String::new();
+        self.reader.read_line(&mut reply)?;
+
+        match reply.trim_end() {
+            "ok" => Ok(()),
+            "" => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "server closed the connection",
+            )),
+            reply => Err(io::Error::other(
+                reply.trim_start_matches("err ").to_string(),
+            )),
+        }
+    }
+}
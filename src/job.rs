@@ -1,25 +1,182 @@
+use crossbeam_channel::SendError;
+use std::sync::Arc;
+
+use crate::outcome::JobMeta;
+
 pub trait Job: Send + 'static {
     fn call(self);
+
+    /// Label under which the pool aggregates `KindStats` for this job.
+    fn kind(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// Anything that runs jobs, so code built on multix can take
+/// `impl Executor<T>` and leave the choice of pool to its caller. A refused
+/// job is handed back.
+pub trait Executor<T: Job = Box<dyn JobBox>> {
+    fn execute(&self, job: T) -> Result<(), SendError<T>>;
 }
 
 pub trait JobBox: Send + 'static {
     fn call_box(self: Box<Self>);
+
+    fn kind_box(&self) -> Option<&'static str>;
 }
 
-impl<F> Job for F where F: FnOnce() + Send + 'static {
-    fn call(self){
+impl<F> Job for F
+where
+    F: FnOnce() + Send + 'static,
+{
+    fn call(self) {
         (self)()
     }
 }
 
+/// A notification for `ThreadPool<()>`: the work is done by the
+/// `TPBuilder::handler` of the worker that picks it up.
+impl Job for () {
+    fn call(self) {}
+}
+
 impl<T: Sized + Job> JobBox for T {
     fn call_box(self: Box<Self>) {
         (*self).call()
     }
+
+    fn kind_box(&self) -> Option<&'static str> {
+        self.kind()
+    }
 }
 
-impl Job for Box<JobBox> {
-    fn call(self: Self) {
+impl Job for Box<dyn JobBox> {
+    fn call(self) {
         self.call_box()
     }
+
+    fn kind(&self) -> Option<&'static str> {
+        (**self).kind_box()
+    }
+}
+
+impl<T: Job, E: Executor<T> + ?Sized> Executor<T> for &E {
+    fn execute(&self, job: T) -> Result<(), SendError<T>> {
+        (**self).execute(job)
+    }
+}
+
+/// Declares an enum whose variants each wrap a `Job` and implements `Job` for
+/// it by dispatching to the wrapped job. Each variant name is reported as the
+/// job kind, so `ThreadPool::kind_stats` breaks work down per variant.
+///
+/// ```
+/// struct Resize(u32);
+/// struct Encode(u32);
+///
+/// impl multix::Job for Resize {
+///     fn call(self) {}
+/// }
+///
+/// impl multix::Job for Encode {
+///     fn call(self) {}
+/// }
+///
+/// multix::job_enum! {
+///     enum Work {
+///         Resize(Resize),
+///         Encode(Encode),
+///     }
+/// }
+///
+/// let pool: multix::ThreadPool<Work> = multix::ThreadPool::new(2);
+/// pool.send(Work::Resize(Resize(64))).unwrap();
+/// ```
+#[macro_export]
+macro_rules! job_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($variant:ident($job:ty)),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant($job)),+
+        }
+
+        impl $crate::Job for $name {
+            fn call(self) {
+                match self {
+                    $($name::$variant(job) => $crate::Job::call(job)),+
+                }
+            }
+
+            fn kind(&self) -> ::std::option::Option<&'static str> {
+                match *self {
+                    $($name::$variant(_) => ::std::option::Option::Some(stringify!($variant))),+
+                }
+            }
+        }
+    };
+}
+
+/// Called by the worker around every job, e.g. to reset FFI or thread state
+/// that a job may leave behind.
+pub trait JobGuard: Send + Sync {
+    fn enter(&self) {}
+
+    fn exit(&self) {}
+
+    /// Called instead of `exit` when the job panicked.
+    fn recover(&self) {
+        self.exit()
+    }
+}
+
+/// Wraps the execution of every job, see `TPBuilder::layer`. A layer runs
+/// the rest of the chain, and finally the job, with `next.run()`; it may
+/// skip it, time it or catch its panic.
+pub trait JobLayer: Send + Sync {
+    fn call(&self, meta: &JobMeta, next: Next);
+}
+
+impl<F> JobLayer for F
+where
+    F: Fn(&JobMeta, Next) + Send + Sync,
+{
+    fn call(&self, meta: &JobMeta, next: Next) {
+        self(meta, next)
+    }
+}
+
+/// The layers left to run around a job, and the job itself.
+pub struct Next<'a> {
+    layers: &'a [Arc<dyn JobLayer>],
+    meta: &'a JobMeta<'a>,
+    job: &'a mut dyn FnMut(),
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        layers: &'a [Arc<dyn JobLayer>],
+        meta: &'a JobMeta<'a>,
+        job: &'a mut dyn FnMut(),
+    ) -> Next<'a> {
+        Next { layers, meta, job }
+    }
+
+    pub fn run(self) {
+        match self.layers.split_first() {
+            Some((layer, layers)) => layer.call(
+                self.meta,
+                Next {
+                    layers,
+                    meta: self.meta,
+                    job: self.job,
+                },
+            ),
+            None => (self.job)(),
+        }
+    }
 }
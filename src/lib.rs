@@ -1,9 +1,74 @@
+pub mod affinity;
+pub mod alloc;
+#[cfg(feature = "alloc-stats")]
+pub mod alloc_stats;
+#[cfg(feature = "arena")]
+pub mod arena;
+#[cfg(feature = "async-std")]
+pub mod async_std;
 pub mod atomic;
+pub mod context;
+pub mod control;
 pub mod core;
+pub mod cpu;
+#[cfg(feature = "persistence")]
+pub mod durable;
+pub mod error;
+#[cfg(feature = "futures")]
+pub mod executor;
+#[cfg(feature = "fault-injection")]
+pub mod fault;
+pub mod handle;
+pub mod idle;
+#[cfg(feature = "metrics")]
+pub mod instrument;
+#[cfg(all(unix, feature = "ipc"))]
+pub mod ipc;
 pub mod job;
 pub mod lifecycle;
+pub mod outcome;
+pub mod priority;
+pub mod progress;
+pub mod registry;
+pub mod rng;
+pub mod router;
+pub mod rt;
+pub mod scope;
+pub mod scratch;
+pub mod sender;
+pub mod slots;
+#[cfg(feature = "smol")]
+pub mod smol;
 pub mod state;
+pub mod stats;
+pub mod task;
+pub mod test;
+pub mod timer;
+#[cfg(feature = "topology")]
+pub mod topology;
+pub mod waker;
 pub mod worker;
 
-pub use self::core::{ThreadPool};
-pub use self::job::{Job, JobBox};
+#[cfg(feature = "arena")]
+pub use self::arena::arena;
+pub use self::context::JobContext;
+pub use self::core::{ForkGuard, Prepared, ShutdownResult, ThreadPool};
+pub use self::error::{
+    ConfigError, Elapsed, GroupTooLarge, JobError, RouteError, RtReject, SubmitError,
+};
+pub use self::handle::{join_all, join_all_timeout, select, JobHandle, OnDrop};
+pub use self::job::{Executor, Job, JobBox, JobGuard, JobLayer, Next};
+pub use self::outcome::{DeadlineMiss, JobMeta, JobOutcome, JobTimestamps};
+pub use self::priority::Priority;
+pub use self::progress::{ProgressReceiver, ProgressSink, ProgressSummary};
+pub use self::registry::{Breadcrumb, JobId, JobStatus};
+pub use self::rng::rng;
+pub use self::router::Router;
+pub use self::rt::RtProducer;
+pub use self::scope::{PanicPolicy, Region, RegionResult, Scope};
+pub use self::scratch::scratch;
+pub use self::sender::PoolSender;
+pub use self::stats::{Histogram, KindStats, PoolDump, PoolStats, WorkerStats};
+pub use self::timer::ScheduleHandle;
+pub use self::waker::waker_for;
+pub use self::worker::WorkerContext;
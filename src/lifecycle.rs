@@ -1,12 +1,18 @@
 pub const LIFECYCLE_BITS: usize = 3;
 pub const LIFECYCLE_MASK: usize = 7;
 
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::fmt;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum Lifecycle {
     Running = 0,
-    Stop = 1,
-    Tidying = 2,
-    Terminated = 3,
+    Shutdown = 1,
+    Stop = 2,
+    Tidying = 3,
+    Terminated = 4,
 }
 
 impl Lifecycle {
@@ -15,10 +21,25 @@ impl Lifecycle {
 
         match val {
             0 => Running,
-            1 => Stop,
-            2 => Tidying,
-            3 => Terminated,
+            1 => Shutdown,
+            2 => Stop,
+            3 => Tidying,
+            4 => Terminated,
             _ => panic!("unexpected state value"),
         }
     }
 }
+
+impl fmt::Display for Lifecycle {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Lifecycle::Running => "running",
+            Lifecycle::Shutdown => "shutdown",
+            Lifecycle::Stop => "stop",
+            Lifecycle::Tidying => "tidying",
+            Lifecycle::Terminated => "terminated",
+        };
+
+        fmt.write_str(name)
+    }
+}
@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "alloc-stats")]
+use crate::alloc_stats::AllocStats;
+use crate::error::JobError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobOutcome {
+    pub id: u64,
+    pub name: Option<String>,
+    pub duration: Duration,
+    /// CPU time spent in the job, see `TPBuilder::cpu_time`.
+    pub cpu: Option<Duration>,
+    pub result: Result<(), JobError>,
+    #[cfg(feature = "alloc-stats")]
+    pub allocated: AllocStats,
+}
+
+/// The job about to run or just finished, see `TPBuilder::before_job`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct JobMeta<'a> {
+    pub id: u64,
+    pub name: Option<&'a str>,
+    pub kind: Option<&'static str>,
+    /// Index of the worker running the job, or `usize::MAX` for a job run
+    /// by the thread that sent it.
+    pub worker: usize,
+}
+
+/// Raw timestamps of one job, see `TPBuilder::record_timestamps`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct JobTimestamps {
+    pub id: u64,
+    pub kind: Option<&'static str>,
+    pub enqueued: Instant,
+    pub dequeued: Instant,
+    pub completed: Instant,
+}
+
+/// A job that finished after the deadline set with `Prepared::deadline`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadlineMiss {
+    pub id: u64,
+    pub name: Option<String>,
+    pub kind: Option<&'static str>,
+    /// How long after the deadline the job finished.
+    pub late: Duration,
+}
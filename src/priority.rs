@@ -0,0 +1,255 @@
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use std::time::Instant;
+
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Select, Sender, TryRecvError};
+
+use crate::task::Task;
+
+/// Order in which workers pick up queued jobs; see
+/// `ThreadPool::send_with_priority`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+// Extra queues for `High` and `Low` jobs next to the pool's main queue, which
+// holds the `Normal` ones. Workers always take from the highest non-empty
+// lane. Both lanes are unbounded, so `queue_capacity` only limits `Normal`.
+//
+// In NUMA mode `Normal` jobs sent from a node go to that node's local queue,
+// and under `Scheduler::WorkStealing` jobs sent from a worker go to that
+// worker's. Workers drain their own local queue before the main one and steal
+// from the other local queues last.
+//
+// The LIFO slots of the workers, see `ThreadPool::send_local`, are kept here
+// too so their jobs count as queued and `close_force` can take them back.
+pub struct Lanes<T> {
+    high: Lane<T>,
+    low: Lane<T>,
+    locals: Vec<Lane<T>>,
+    slots: Mutex<Vec<Arc<Slot<T>>>>,
+    filled: AtomicUsize,
+}
+
+type Lane<T> = (Sender<Task<T>>, Receiver<Task<T>>);
+
+pub type Slot<T> = Mutex<Option<Task<T>>>;
+
+impl<T> Lanes<T> {
+    pub fn new() -> Lanes<T> {
+        Lanes::with_locals(0)
+    }
+
+    pub fn with_locals(locals: usize) -> Lanes<T> {
+        Lanes {
+            high: unbounded(),
+            low: unbounded(),
+            locals: (0..locals).map(|_| unbounded()).collect(),
+            slots: Mutex::new(Vec::new()),
+            filled: AtomicUsize::new(0),
+        }
+    }
+
+    /// Registers an empty LIFO slot for a new worker.
+    pub fn add_slot(&self) -> Arc<Slot<T>> {
+        let slot = Arc::new(Mutex::new(None));
+        self.slots.lock().unwrap().push(slot.clone());
+
+        slot
+    }
+
+    /// Unregisters `slot` and returns the task it still held.
+    pub fn remove_slot(&self, slot: &Arc<Slot<T>>) -> Option<Task<T>> {
+        self.slots
+            .lock()
+            .unwrap()
+            .retain(|other| !Arc::ptr_eq(other, slot));
+
+        self.take_slot(slot)
+    }
+
+    /// Puts `task` in `slot` and returns the task that was there.
+    pub fn replace_slot(&self, slot: &Slot<T>, task: Task<T>) -> Option<Task<T>> {
+        let prev = slot.lock().unwrap().replace(task);
+
+        if prev.is_none() {
+            self.filled.fetch_add(1, Ordering::SeqCst);
+        }
+
+        prev
+    }
+
+    pub fn take_slot(&self, slot: &Slot<T>) -> Option<Task<T>> {
+        if self.filled.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+
+        let task = slot.lock().unwrap().take();
+
+        if task.is_some() {
+            self.filled.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        task
+    }
+
+    /// Forgets the slots of workers that are gone, e.g. in a forked child,
+    /// moving their tasks to the `High` lane.
+    pub fn reset_slots(&self) {
+        let slots = mem::take(&mut *self.slots.lock().unwrap());
+
+        for slot in slots {
+            if let Some(task) = self.take_slot(&slot) {
+                self.push(Priority::High, task);
+            }
+        }
+    }
+
+    /// Takes every task out of the slots and the queues, `normal` being the
+    /// main one.
+    pub fn drain(&self, normal: &Receiver<Task<T>>) -> Vec<Task<T>> {
+        let mut tasks: Vec<_> = self
+            .slots
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|slot| self.take_slot(slot))
+            .collect();
+
+        for rx in self.receivers(normal) {
+            tasks.extend(rx.try_iter());
+        }
+
+        tasks
+    }
+
+    /// Queues a `Normal` task on local queue `local`.
+    pub fn push_local(&self, local: usize, task: Task<T>) {
+        let _ = self.locals[local].0.send(task);
+    }
+
+    /// Queues a `High` or `Low` task.
+    pub fn push(&self, priority: Priority, task: Task<T>) {
+        let lane = match priority {
+            Priority::High => &self.high.0,
+            Priority::Low => &self.low.0,
+            Priority::Normal => unreachable!("normal jobs go to the main queue"),
+        };
+
+        // The lanes hold their own receivers and never disconnect.
+        let _ = lane.send(task);
+    }
+
+    /// Takes a task from the highest non-empty queue, `normal` being the
+    /// main one. `Normal` tasks come from the `local` queue first and from
+    /// the other local queues last. Errors are those of `normal`.
+    pub fn try_recv(
+        &self,
+        normal: &Receiver<Task<T>>,
+        local: Option<usize>,
+    ) -> Result<Task<T>, TryRecvError> {
+        if let Ok(task) = self.high.1.try_recv() {
+            return Ok(task);
+        }
+
+        let local = local.filter(|&local| local < self.locals.len());
+
+        if let Some(Ok(task)) = local.map(|local| self.locals[local].1.try_recv()) {
+            return Ok(task);
+        }
+
+        let err = match normal.try_recv() {
+            Ok(task) => return Ok(task),
+            Err(err) => err,
+        };
+
+        // Steals from the other local queues, starting with the next one so
+        // idle workers do not all pile on the same queue.
+        let start = local.map_or(0, |local| local + 1);
+        for i in 0..self.locals.len() {
+            let victim = (start + i) % self.locals.len();
+
+            if Some(victim) == local {
+                continue;
+            }
+
+            if let Ok(task) = self.locals[victim].1.try_recv() {
+                return Ok(task);
+            }
+        }
+
+        self.low.1.try_recv().map_err(|_| err)
+    }
+
+    /// Waits until `deadline` for any queue or `signal` to be ready, then takes
+    /// from the highest queue like `try_recv`, so a task in a higher lane is
+    /// never passed over for the one the select happened to pick. `Ok(None)`
+    /// when `signal` woke it or another consumer took the task first.
+    pub fn recv(
+        &self,
+        normal: &Receiver<Task<T>>,
+        local: Option<usize>,
+        signal: &Receiver<()>,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Task<T>>, RecvTimeoutError> {
+        match self.try_recv(normal, local) {
+            Ok(task) => return Ok(Some(task)),
+            Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+            Err(TryRecvError::Empty) => {}
+        }
+
+        let mut select = Select::new();
+        for rx in self.receivers(normal) {
+            select.recv(rx);
+        }
+        select.recv(signal);
+
+        match deadline {
+            Some(deadline) => {
+                if select.ready_deadline(deadline).is_err() {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+            None => {
+                select.ready();
+            }
+        }
+
+        match self.try_recv(normal, local) {
+            Ok(task) => Ok(Some(task)),
+            Err(TryRecvError::Disconnected) => Err(RecvTimeoutError::Disconnected),
+            Err(TryRecvError::Empty) => Ok(None),
+        }
+    }
+
+    /// Every queue from highest to lowest priority.
+    pub fn receivers<'a>(&'a self, normal: &'a Receiver<Task<T>>) -> Vec<&'a Receiver<Task<T>>> {
+        let mut receivers = vec![&self.high.1, normal];
+        receivers.extend(self.locals.iter().map(|local| &local.1));
+        receivers.push(&self.low.1);
+
+        receivers
+    }
+
+    pub fn len(&self) -> usize {
+        let locals: usize = self.locals.iter().map(|local| local.1.len()).sum();
+
+        self.high.1.len() + self.low.1.len() + locals + self.filled.load(Ordering::SeqCst)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Default for Lanes<T> {
+    fn default() -> Lanes<T> {
+        Lanes::new()
+    }
+}
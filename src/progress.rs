@@ -0,0 +1,114 @@
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Passed to a job started with `ThreadPool::send_with_progress` to report
+/// progress to its `ProgressReceiver`.
+#[derive(Debug)]
+pub struct ProgressSink<P> {
+    tx: Sender<P>,
+    fraction: Arc<AtomicU32>,
+}
+
+/// Receives the progress a job reports. It disconnects once the job has
+/// finished, so iterating over it ends with the job.
+#[derive(Debug)]
+pub struct ProgressReceiver<P> {
+    rx: Receiver<P>,
+}
+
+/// Snapshot of a pool's progress, see `ThreadPool::progress_summary`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ProgressSummary {
+    pub queued: usize,
+    pub in_flight: usize,
+    pub completed: u64,
+    /// Fractions reported through `ProgressSink::set_fraction` by jobs that
+    /// have not finished yet.
+    pub fractions: Vec<f32>,
+}
+
+impl ProgressSummary {
+    /// Overall progress between 0 and 1, counting finished jobs as done,
+    /// running ones by their reported fraction and queued ones as not started.
+    pub fn fraction(&self) -> f32 {
+        let total = self.completed as f32 + (self.in_flight + self.queued) as f32;
+
+        if total == 0.0 {
+            return 1.0;
+        }
+
+        (self.completed as f32 + self.fractions.iter().sum::<f32>()) / total
+    }
+}
+
+// Fractions of running jobs. Entries go away with the job's sink.
+pub type Fractions = Mutex<Vec<Weak<AtomicU32>>>;
+
+pub fn channel<P>(fractions: &Fractions) -> (ProgressSink<P>, ProgressReceiver<P>) {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let fraction = Arc::new(AtomicU32::new(0f32.to_bits()));
+
+    {
+        let mut fractions = fractions.lock().unwrap();
+        fractions.retain(|f| f.strong_count() > 0);
+        fractions.push(Arc::downgrade(&fraction));
+    }
+
+    (ProgressSink { tx, fraction }, ProgressReceiver { rx })
+}
+
+pub fn fractions(fractions: &Fractions) -> Vec<f32> {
+    let mut fractions = fractions.lock().unwrap();
+    fractions.retain(|f| f.strong_count() > 0);
+
+    fractions
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|f| f32::from_bits(f.load(Ordering::Relaxed)))
+        .collect()
+}
+
+impl<P> ProgressSink<P> {
+    /// Reports `progress`. Reports are dropped if the receiver is gone.
+    pub fn report(&self, progress: P) {
+        let _ = self.tx.send(progress);
+    }
+
+    /// Sets how far along the job is, from 0 to 1, for
+    /// `ThreadPool::progress_summary`.
+    pub fn set_fraction(&self, fraction: f32) {
+        let fraction = fraction.clamp(0.0, 1.0);
+
+        self.fraction.store(fraction.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl<P> ProgressReceiver<P> {
+    /// Waits for the next report; `None` once the job has finished.
+    pub fn recv(&self) -> Option<P> {
+        self.rx.recv().ok()
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<P, RecvTimeoutError> {
+        self.rx.recv_timeout(timeout)
+    }
+
+    pub fn try_recv(&self) -> Result<P, TryRecvError> {
+        self.rx.try_recv()
+    }
+
+    /// Drains pending reports and returns the most recent one.
+    pub fn latest(&self) -> Option<P> {
+        self.rx.try_iter().last()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = P> + '_ {
+        self.rx.iter()
+    }
+}
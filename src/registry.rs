@@ -0,0 +1,181 @@
+#[cfg(feature = "backtrace")]
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::SystemTime;
+
+use crate::error::JobError;
+
+/// Identifies a job sent with `ThreadPool::send_tracked`. The number is the
+/// same id found in `JobOutcome` and `JobTimestamps`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct JobId(pub u64);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Panicked,
+    /// Dropped without running, e.g. by `close_force` or a discarding
+    /// rejection policy.
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        !matches!(self, JobStatus::Queued | JobStatus::Running)
+    }
+}
+
+/// Where and when a queued job was sent, see `TPBuilder::breadcrumbs`.
+#[derive(Debug, Clone)]
+pub struct Breadcrumb {
+    pub id: JobId,
+    pub thread: ThreadId,
+    pub thread_name: Option<String>,
+    pub sent_at: SystemTime,
+    #[cfg(feature = "backtrace")]
+    pub backtrace: Arc<Backtrace>,
+}
+
+impl Breadcrumb {
+    fn capture(id: u64) -> Breadcrumb {
+        let thread = thread::current();
+
+        Breadcrumb {
+            id: JobId(id),
+            thread: thread.id(),
+            thread_name: thread.name().map(str::to_string),
+            sent_at: SystemTime::now(),
+            #[cfg(feature = "backtrace")]
+            backtrace: Arc::new(Backtrace::force_capture()),
+        }
+    }
+}
+
+// Statuses of tracked jobs, which stay until `ThreadPool::forget`, and
+// breadcrumbs of jobs not yet dequeued, flagged once they are queued.
+#[derive(Default)]
+pub struct Registry {
+    jobs: Mutex<HashMap<u64, JobStatus>>,
+    crumbs: Mutex<HashMap<u64, (Breadcrumb, bool)>>,
+}
+
+impl Registry {
+    pub fn track(self: &Arc<Self>, id: u64) -> Tracker {
+        let mut tracker = Tracker {
+            registry: self.clone(),
+            id,
+            status: JobStatus::Queued,
+            tracked: false,
+            crumb: false,
+        };
+        tracker.track();
+
+        tracker
+    }
+
+    /// Captures a breadcrumb for the job, listed from when it is queued
+    /// until it is dequeued; see `Tracker::queued`.
+    pub fn trail(self: &Arc<Self>, id: u64) -> Tracker {
+        self.crumbs
+            .lock()
+            .unwrap()
+            .insert(id, (Breadcrumb::capture(id), false));
+
+        Tracker {
+            registry: self.clone(),
+            id,
+            status: JobStatus::Queued,
+            tracked: false,
+            crumb: true,
+        }
+    }
+
+    pub fn breadcrumbs(&self) -> Vec<Breadcrumb> {
+        let mut crumbs: Vec<_> = self
+            .crumbs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|(_, queued)| *queued)
+            .map(|(crumb, _)| crumb.clone())
+            .collect();
+        crumbs.sort_by_key(|crumb| crumb.id);
+
+        crumbs
+    }
+
+    pub fn status(&self, id: u64) -> Option<JobStatus> {
+        self.jobs.lock().unwrap().get(&id).copied()
+    }
+
+    pub fn forget(&self, id: u64) -> Option<JobStatus> {
+        self.jobs.lock().unwrap().remove(&id)
+    }
+
+    fn set(&self, id: u64, status: JobStatus) {
+        self.jobs.lock().unwrap().insert(id, status);
+    }
+}
+
+// Travels with a tracked task or one leaving a breadcrumb. If it is dropped
+// before the job finished the job never ran and is marked cancelled.
+pub struct Tracker {
+    registry: Arc<Registry>,
+    id: u64,
+    status: JobStatus,
+    tracked: bool,
+    crumb: bool,
+}
+
+impl Tracker {
+    /// Records the job's status as well, see `ThreadPool::send_tracked`.
+    pub fn track(&mut self) {
+        self.tracked = true;
+        self.registry.set(self.id, self.status);
+    }
+
+    /// Lists the job's breadcrumb, if any, now that the job is being queued.
+    pub fn queued(&mut self) {
+        if self.crumb {
+            if let Some(crumb) = self.registry.crumbs.lock().unwrap().get_mut(&self.id) {
+                crumb.1 = true;
+            }
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.update(JobStatus::Running);
+    }
+
+    pub fn finish(&mut self, result: &Result<(), JobError>) {
+        self.update(match result {
+            Ok(()) => JobStatus::Completed,
+            Err(_) => JobStatus::Panicked,
+        });
+    }
+
+    fn update(&mut self, status: JobStatus) {
+        self.status = status;
+
+        if self.tracked {
+            self.registry.set(self.id, status);
+        }
+
+        if self.crumb {
+            self.crumb = false;
+            self.registry.crumbs.lock().unwrap().remove(&self.id);
+        }
+    }
+}
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        if !self.status.is_finished() {
+            self.update(JobStatus::Cancelled);
+        }
+    }
+}
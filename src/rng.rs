@@ -0,0 +1,50 @@
+use std::cell::Cell;
+
+pub const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+thread_local! {
+    static STATE: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// SplitMix64 generator owned by the current worker, returned on drop.
+pub struct Rng {
+    state: u64,
+}
+
+/// Returns the generator of the current worker if the pool was built with
+/// `TPBuilder::seed_rng` and the generator is not already borrowed.
+pub fn rng() -> Option<Rng> {
+    STATE.with(|slot| slot.take()).map(|state| Rng { state })
+}
+
+pub fn install(seed: u64) {
+    STATE.with(|slot| slot.set(Some(seed)));
+}
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GAMMA);
+        mix(self.state)
+    }
+
+    /// Uniform value in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        unit(self.next_u64())
+    }
+}
+
+impl Drop for Rng {
+    fn drop(&mut self) {
+        install(self.state);
+    }
+}
+
+pub fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub fn unit(value: u64) -> f64 {
+    (value >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,111 @@
+use std::borrow::Borrow;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::core::ThreadPool;
+use crate::error::RouteError;
+use crate::job::{Executor, Job};
+use crate::stats::PoolStats;
+use crossbeam_channel::SendError;
+
+/// Owns several pools keyed by name and sends each job to the pool picked by
+/// a routing function, so the decision of where work runs lives in one
+/// place. Closing the router closes every pool.
+pub struct Router<T, K = String> {
+    pools: Vec<(K, ThreadPool<T>)>,
+    route: Arc<dyn Fn(&T) -> K + Send + Sync>,
+}
+
+impl<T: Job, K: Eq> Router<T, K> {
+    pub fn new<F>(route: F) -> Router<T, K>
+    where
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        Router {
+            pools: Vec::new(),
+            route: Arc::new(route),
+        }
+    }
+
+    /// Adds `pool` under `key`, returning the pool it replaces.
+    pub fn add(&mut self, key: K, pool: ThreadPool<T>) -> Option<ThreadPool<T>> {
+        match self.pools.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, old)) => Some(mem::replace(old, pool)),
+            None => {
+                self.pools.push((key, pool));
+                None
+            }
+        }
+    }
+
+    pub fn pool<Q>(&self, key: &Q) -> Option<&ThreadPool<T>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.pools
+            .iter()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, pool)| pool)
+    }
+
+    pub fn send(&self, job: T) -> Result<(), RouteError<T>> {
+        let key = (self.route)(&job);
+
+        match self.pool(&key) {
+            Some(pool) => pool
+                .send(job)
+                .map_err(|err| RouteError::Closed(err.into_inner())),
+            None => Err(RouteError::Unrouted(job)),
+        }
+    }
+
+    /// Stats of every pool, in the order they were added.
+    pub fn stats(&self) -> Vec<(&K, PoolStats)> {
+        self.pools
+            .iter()
+            .map(|(key, pool)| (key, pool.stats()))
+            .collect()
+    }
+
+    pub fn queued(&self) -> usize {
+        self.pools.iter().map(|(_, pool)| pool.queued()).sum()
+    }
+
+    pub fn close(&self) {
+        for (_, pool) in &self.pools {
+            pool.close();
+        }
+    }
+
+    pub fn await_termination(&self) {
+        for (_, pool) in &self.pools {
+            pool.await_termination();
+        }
+    }
+
+    /// Waits up to `timeout` overall for every pool to terminate.
+    pub fn await_termination_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        self.pools.iter().all(|(_, pool)| {
+            pool.await_termination_timeout(deadline.saturating_duration_since(Instant::now()))
+        })
+    }
+}
+
+impl<T: Job, K: Eq> Executor<T> for Router<T, K> {
+    fn execute(&self, job: T) -> Result<(), SendError<T>> {
+        self.send(job).map_err(|err| SendError(err.into_inner()))
+    }
+}
+
+impl<T, K: Clone> Clone for Router<T, K> {
+    fn clone(&self) -> Self {
+        Router {
+            pools: self.pools.clone(),
+            route: self.route.clone(),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, Thread};
+
+use crate::core::{Inner, ThreadPool};
+use crate::error::RtReject;
+use crate::job::Job;
+
+/// Submits jobs from a realtime thread, see `ThreadPool::rt_producer`.
+///
+/// `send_from_rt` only writes to a ring allocated up front and unparks the
+/// thread forwarding the ring to the pool: it never allocates, locks or
+/// spawns. A producer serves a single thread, so it is `Send` but not `Sync`.
+pub struct RtProducer<T> {
+    ring: Arc<Ring<T>>,
+    inner: Arc<Inner>,
+    forwarder: Thread,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+// Single producer, single consumer ring. `tail` is only written by the
+// producer and `head` only by the forwarder; both only ever grow.
+struct Ring<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped: AtomicBool,
+}
+
+unsafe impl<T: Send> Send for Ring<T> {}
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T> Ring<T> {
+    fn push(&self, job: T) -> Result<(), T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+
+        if tail - self.head.load(Ordering::Acquire) == self.slots.len() {
+            return Err(job);
+        }
+
+        unsafe {
+            (*self.slots[tail % self.slots.len()].get()).write(job);
+        }
+        self.tail.store(tail + 1, Ordering::Release);
+
+        Ok(())
+    }
+
+    fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+
+        if head == self.tail.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let job = unsafe { (*self.slots[head % self.slots.len()].get()).assume_init_read() };
+        self.head.store(head + 1, Ordering::Release);
+
+        Some(job)
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+pub fn producer<T: Job>(pool: ThreadPool<T>, inner: Arc<Inner>, capacity: usize) -> RtProducer<T> {
+    assert!(capacity > 0, "realtime ring needs room for one job");
+
+    let ring = Arc::new(Ring {
+        slots: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        dropped: AtomicBool::new(false),
+    });
+
+    let forwarder = {
+        let ring = ring.clone();
+
+        thread::Builder::new()
+            .name("multix-rt".to_string())
+            .spawn(move || forward(pool, &ring))
+            .unwrap()
+    };
+
+    RtProducer {
+        ring,
+        inner,
+        forwarder: forwarder.thread().clone(),
+        _not_sync: PhantomData,
+    }
+}
+
+fn forward<T: Job>(pool: ThreadPool<T>, ring: &Ring<T>) {
+    loop {
+        // Checked before draining so jobs pushed right before the producer
+        // was dropped are still forwarded.
+        let dropped = ring.dropped.load(Ordering::Acquire);
+
+        // Jobs the pool refuses once it is closed are dropped.
+        while let Some(job) = ring.pop() {
+            let _ = pool.send(job);
+        }
+
+        if dropped {
+            return;
+        }
+
+        thread::park();
+    }
+}
+
+impl<T> RtProducer<T> {
+    pub fn send_from_rt(&self, job: T) -> Result<(), RtReject<T>> {
+        if self.inner.state.load().is_shutdown() {
+            return Err(RtReject::Closed(job));
+        }
+
+        self.ring.push(job).map_err(RtReject::Full)?;
+        self.forwarder.unpark();
+
+        Ok(())
+    }
+}
+
+impl<T> Drop for RtProducer<T> {
+    fn drop(&mut self) {
+        self.ring.dropped.store(true, Ordering::Release);
+        self.forwarder.unpark();
+    }
+}
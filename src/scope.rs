@@ -0,0 +1,328 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::core::ThreadPool;
+use crate::error::JobError;
+use crate::job::{Job, JobBox};
+
+/// Submits jobs that may borrow from outside the scope; see
+/// `ThreadPool::scope`.
+pub struct Scope<'scope, 'env: 'scope> {
+    jobs: Jobs<'scope>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+/// Spawns jobs tied to a region; see `ThreadPool::region`.
+pub struct Region<'scope, 'env: 'scope> {
+    jobs: Jobs<'scope>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+/// What a region does once one of its jobs panics.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum PanicPolicy {
+    /// Keeps running the other jobs.
+    #[default]
+    Continue,
+    /// Skips every job that has not started yet, like `Region::cancel`.
+    CancelRemaining,
+    /// Lets jobs already spawned run but refuses new ones, which are
+    /// reported as `JobError::Cancelled`, and marks the result poisoned.
+    Poison,
+}
+
+/// What happened to the jobs spawned in a region.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegionResult {
+    pub completed: usize,
+    pub errors: Vec<JobError>,
+    /// A job panicked under `PanicPolicy::Poison`.
+    pub poisoned: bool,
+}
+
+impl RegionResult {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+struct Jobs<'scope> {
+    pool: &'scope ThreadPool<Box<dyn JobBox>>,
+    state: Arc<State>,
+    scope: PhantomData<&'scope mut &'scope ()>,
+}
+
+struct State {
+    pending: Mutex<usize>,
+    done: Condvar,
+    cancelled: AtomicBool,
+    poisoned: AtomicBool,
+    policy: PanicPolicy,
+    result: Mutex<RegionResult>,
+    // Jobs the pool dropped unrun, left for the scope's thread to run.
+    orphans: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+}
+
+// Held by every job of a scope. It is dropped whether the job runs, panics or
+// is discarded by the pool, so the scope never waits on a job that is gone.
+struct Pending {
+    state: Arc<State>,
+    result: Option<Result<(), JobError>>,
+}
+
+impl Pending {
+    fn finish(&mut self, result: Result<(), JobError>) {
+        self.result = Some(result);
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        {
+            let mut result = self.state.result.lock().unwrap();
+
+            match self.result.take().unwrap_or(Err(JobError::Cancelled)) {
+                Ok(()) => result.completed += 1,
+                Err(err) => result.errors.push(err),
+            }
+        }
+
+        let mut pending = self.state.pending.lock().unwrap();
+        *pending -= 1;
+
+        if *pending == 0 {
+            self.state.done.notify_all();
+        }
+    }
+}
+
+// A scope job on its way to a worker. If the pool drops it unrun, e.g. under
+// a discarding rejection policy or in `close_force`, it goes back to the
+// scope.
+struct Orphan {
+    job: Option<Box<dyn FnOnce() + Send>>,
+    state: Arc<State>,
+}
+
+impl Drop for Orphan {
+    fn drop(&mut self) {
+        if let Some(job) = self.job.take() {
+            let _pending = self.state.pending.lock().unwrap();
+
+            self.state.orphans.lock().unwrap().push(job);
+            self.state.done.notify_all();
+        }
+    }
+}
+
+impl State {
+    fn panicked(&self) {
+        match self.policy {
+            PanicPolicy::Continue => {}
+            PanicPolicy::CancelRemaining => self.cancelled.store(true, Ordering::Relaxed),
+            PanicPolicy::Poison => self.poisoned.store(true, Ordering::Relaxed),
+        }
+    }
+}
+
+impl<'scope> Jobs<'scope> {
+    fn new(pool: &'scope ThreadPool<Box<dyn JobBox>>, policy: PanicPolicy) -> Jobs<'scope> {
+        Jobs {
+            pool,
+            state: Arc::new(State {
+                pending: Mutex::new(0),
+                done: Condvar::new(),
+                cancelled: AtomicBool::new(false),
+                poisoned: AtomicBool::new(false),
+                policy,
+                result: Mutex::new(RegionResult::default()),
+                orphans: Mutex::new(Vec::new()),
+            }),
+            scope: PhantomData,
+        }
+    }
+
+    fn send<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'scope,
+    {
+        *self.state.pending.lock().unwrap() += 1;
+
+        let mut pending = Pending {
+            state: self.state.clone(),
+            result: None,
+        };
+
+        // Dropping it unrun reports the job as cancelled.
+        if self.state.poisoned.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            if pending.state.cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+
+            match panic::catch_unwind(AssertUnwindSafe(job)) {
+                Ok(()) => pending.finish(Ok(())),
+                Err(payload) => {
+                    pending.finish(Err(JobError::from_panic(&*payload)));
+                    pending.state.panicked();
+                    panic::resume_unwind(payload);
+                }
+            }
+        });
+        // The scope does not return before `Pending` is dropped, which happens
+        // only once the job has run or been discarded, so it never outlives
+        // the borrows it captures.
+        let job: Box<dyn FnOnce() + Send + 'static> = unsafe { mem::transmute(job) };
+        let mut orphan = Orphan {
+            job: Some(job),
+            state: self.state.clone(),
+        };
+
+        if let Err(job) = self.pool.send_scoped(move || {
+            if let Some(job) = orphan.job.take() {
+                job();
+            }
+        }) {
+            job.call();
+        }
+    }
+
+    fn wait(&self) -> RegionResult {
+        let helping = self.pool.is_worker();
+        let _paused = self.pool.pause();
+        let mut pending = self.state.pending.lock().unwrap();
+
+        while *pending > 0 {
+            let orphans = mem::take(&mut *self.state.orphans.lock().unwrap());
+
+            if !orphans.is_empty() {
+                drop(pending);
+                for job in orphans {
+                    // Its panic is already in the result.
+                    let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                }
+                pending = self.state.pending.lock().unwrap();
+                continue;
+            }
+
+            if !helping {
+                pending = self.state.done.wait(pending).unwrap();
+                continue;
+            }
+
+            // A worker of the pool runs queued jobs, the scope's among them,
+            // instead of blocking. With none queued it checks again shortly,
+            // as the remaining jobs may send more.
+            drop(pending);
+            let ran = self.pool.run_queued();
+            pending = self.state.pending.lock().unwrap();
+
+            if !ran && *pending > 0 {
+                pending = self
+                    .state
+                    .done
+                    .wait_timeout(pending, Duration::from_millis(1))
+                    .unwrap()
+                    .0;
+            }
+        }
+
+        let mut result = mem::take(&mut *self.state.result.lock().unwrap());
+        result.poisoned = self.state.poisoned.load(Ordering::Relaxed);
+
+        result
+    }
+
+    fn run<T, R>(&self, f: impl FnOnce() -> R, done: impl FnOnce(R, RegionResult) -> T) -> T {
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        let region = self.wait();
+
+        match result {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(result) => done(result, region),
+        }
+    }
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Sends `job` to the pool. If the pool refuses it, e.g. because it is
+    /// shutting down, or drops it unrun, the job runs on the thread that
+    /// opened the scope instead.
+    pub fn send<F>(&'scope self, job: F)
+    where
+        F: FnOnce() + Send + 'scope,
+    {
+        self.jobs.send(job)
+    }
+}
+
+impl<'scope, 'env> Region<'scope, 'env> {
+    /// Spawns `job` on the pool. If the pool refuses it or drops it unrun,
+    /// the job runs on the thread that opened the region instead.
+    pub fn spawn<F>(&'scope self, job: F)
+    where
+        F: FnOnce() + Send + 'scope,
+    {
+        self.jobs.send(job)
+    }
+
+    /// Skips every job of the region that has not started yet. Skipped jobs
+    /// are reported as `JobError::Cancelled`.
+    pub fn cancel(&self) {
+        self.jobs.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether a job panicked under `PanicPolicy::Poison`.
+    pub fn is_poisoned(&self) -> bool {
+        self.jobs.state.poisoned.load(Ordering::Relaxed)
+    }
+}
+
+pub fn scope<'env, F, R>(pool: &ThreadPool<Box<dyn JobBox>>, f: F) -> R
+where
+    F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+{
+    let scope = Scope {
+        jobs: Jobs::new(pool, PanicPolicy::Continue),
+        env: PhantomData,
+    };
+
+    scope.jobs.run(
+        || f(&scope),
+        |result, region| {
+            let panicked = region
+                .errors
+                .iter()
+                .any(|err| matches!(err, JobError::Panicked(_)));
+
+            if panicked {
+                panic!("a scoped job panicked");
+            }
+
+            result
+        },
+    )
+}
+
+pub fn region<'env, F>(
+    pool: &ThreadPool<Box<dyn JobBox>>,
+    policy: PanicPolicy,
+    f: F,
+) -> RegionResult
+where
+    F: for<'scope> FnOnce(&'scope Region<'scope, 'env>),
+{
+    let region = Region {
+        jobs: Jobs::new(pool, policy),
+        env: PhantomData,
+    };
+
+    region.jobs.run(|| f(&region), |(), result| result)
+}
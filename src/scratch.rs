@@ -0,0 +1,54 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+
+thread_local! {
+    static SCRATCH: RefCell<Option<Box<dyn Any>>> = const { RefCell::new(None) };
+}
+
+/// Borrow of the current worker's scratch value, returned to the worker on drop.
+pub struct Scratch<S: Any> {
+    value: Option<Box<S>>,
+}
+
+/// Returns the scratch value of the current worker if it was built by
+/// `TPBuilder::scratch` with type `S` and is not already borrowed.
+pub fn scratch<S: Any>() -> Option<Scratch<S>> {
+    SCRATCH.with(|slot| {
+        let value = slot.borrow_mut().take()?;
+
+        match value.downcast::<S>() {
+            Ok(value) => Some(Scratch { value: Some(value) }),
+            Err(value) => {
+                *slot.borrow_mut() = Some(value);
+                None
+            }
+        }
+    })
+}
+
+pub fn install(value: Box<dyn Any>) {
+    SCRATCH.with(|slot| *slot.borrow_mut() = Some(value));
+}
+
+impl<S: Any> Deref for Scratch<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<S: Any> DerefMut for Scratch<S> {
+    fn deref_mut(&mut self) -> &mut S {
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<S: Any> Drop for Scratch<S> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            install(value);
+        }
+    }
+}
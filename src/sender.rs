@@ -0,0 +1,196 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::{
+    core::ThreadPool,
+    error::SubmitError,
+    job::{Executor, Job},
+};
+use crossbeam_channel::SendError;
+
+/// Submission handle for a single producer.
+///
+/// A sender with a budget allows at most that many of its jobs to be queued
+/// or running at once; further submissions wait in `acquire`. Clones get a
+/// fresh budget of the same size.
+pub struct PoolSender<T> {
+    pool: ThreadPool<T>,
+    budget: Option<Arc<Budget>>,
+}
+
+struct Budget {
+    limit: usize,
+    state: Mutex<BudgetState>,
+    freed: Condvar,
+}
+
+#[derive(Default)]
+struct BudgetState {
+    outstanding: usize,
+    wakers: Vec<Waker>,
+}
+
+/// Budget slot held by a submitted job until it finishes or is dropped.
+pub struct Lease {
+    budget: Arc<Budget>,
+}
+
+#[must_use = "the budget slot is released when the permit is dropped"]
+pub struct Permit<'a, T> {
+    sender: &'a PoolSender<T>,
+    lease: Option<Lease>,
+}
+
+/// Future returned by `PoolSender::acquire_async`.
+pub struct Acquire<'a, T> {
+    sender: &'a PoolSender<T>,
+}
+
+impl<T: Job> PoolSender<T> {
+    pub fn new(pool: ThreadPool<T>) -> PoolSender<T> {
+        PoolSender { pool, budget: None }
+    }
+
+    /// Panics if `limit` is 0, as `acquire` would then never return.
+    pub fn with_budget(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "a sender budget must allow at least one job");
+
+        self.budget = Some(Arc::new(Budget::new(limit)));
+        self
+    }
+
+    pub fn outstanding(&self) -> usize {
+        self.budget
+            .as_ref()
+            .map_or(0, |budget| budget.state.lock().unwrap().outstanding)
+    }
+
+    pub fn acquire(&self) -> Permit<'_, T> {
+        let lease = self.budget.as_ref().map(|budget| budget.take());
+
+        Permit {
+            sender: self,
+            lease,
+        }
+    }
+
+    pub fn try_acquire(&self) -> Option<Permit<'_, T>> {
+        let lease = match self.budget {
+            Some(ref budget) => Some(budget.try_take(None)?),
+            None => None,
+        };
+
+        Some(Permit {
+            sender: self,
+            lease,
+        })
+    }
+
+    pub fn acquire_async(&self) -> Acquire<'_, T> {
+        Acquire { sender: self }
+    }
+
+    pub fn send(&self, job: T) -> Result<(), SubmitError<T>> {
+        self.acquire().send(job)
+    }
+}
+
+impl<T: Job> Executor<T> for PoolSender<T> {
+    fn execute(&self, job: T) -> Result<(), SendError<T>> {
+        self.send(job).map_err(|err| SendError(err.into_inner()))
+    }
+}
+
+impl<T> Clone for PoolSender<T> {
+    fn clone(&self) -> Self {
+        PoolSender {
+            pool: self.pool.clone(),
+            budget: self
+                .budget
+                .as_ref()
+                .map(|budget| Arc::new(Budget::new(budget.limit))),
+        }
+    }
+}
+
+impl<'a, T: Job> Permit<'a, T> {
+    pub fn send(self, job: T) -> Result<(), SubmitError<T>> {
+        self.sender.pool.send_leased(job, self.lease)
+    }
+}
+
+impl<'a, T: Job> Future for Acquire<'a, T> {
+    type Output = Permit<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Permit<'a, T>> {
+        let sender = self.sender;
+        let lease = match sender.budget {
+            Some(ref budget) => match budget.try_take(Some(cx.waker())) {
+                Some(lease) => Some(lease),
+                None => return Poll::Pending,
+            },
+            None => None,
+        };
+
+        Poll::Ready(Permit { sender, lease })
+    }
+}
+
+impl Budget {
+    fn new(limit: usize) -> Budget {
+        Budget {
+            limit,
+            state: Mutex::new(BudgetState::default()),
+            freed: Condvar::new(),
+        }
+    }
+
+    fn take(self: &Arc<Self>) -> Lease {
+        let mut state = self.state.lock().unwrap();
+
+        while state.outstanding >= self.limit {
+            state = self.freed.wait(state).unwrap();
+        }
+
+        state.outstanding += 1;
+        Lease {
+            budget: self.clone(),
+        }
+    }
+
+    fn try_take(self: &Arc<Self>, waker: Option<&Waker>) -> Option<Lease> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.outstanding >= self.limit {
+            if let Some(waker) = waker {
+                state.wakers.push(waker.clone());
+            }
+
+            return None;
+        }
+
+        state.outstanding += 1;
+        Some(Lease {
+            budget: self.clone(),
+        })
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.budget.state.lock().unwrap();
+
+            state.outstanding -= 1;
+            std::mem::take(&mut state.wakers)
+        };
+
+        self.budget.freed.notify_one();
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
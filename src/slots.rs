@@ -0,0 +1,79 @@
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// Preallocated payload buffers owned by a pool.
+pub struct Slots {
+    tx: Sender<(usize, Vec<u8>)>,
+    rx: Receiver<(usize, Vec<u8>)>,
+}
+
+/// A claimed payload buffer. Moving it into a job moves only the handle; the
+/// buffer returns to the pool, cleared, when the slot is dropped.
+pub struct Slot {
+    index: usize,
+    buf: Vec<u8>,
+    tx: Sender<(usize, Vec<u8>)>,
+}
+
+impl Slots {
+    pub fn new(count: usize, capacity: usize) -> Slots {
+        let (tx, rx) = bounded(count);
+
+        for index in 0..count {
+            tx.send((index, Vec::with_capacity(capacity))).unwrap();
+        }
+
+        Slots { tx, rx }
+    }
+
+    pub fn claim(&self) -> Option<Slot> {
+        self.rx.try_recv().ok().map(|(index, buf)| Slot {
+            index,
+            buf,
+            tx: self.tx.clone(),
+        })
+    }
+
+    pub fn free(&self) -> usize {
+        self.rx.len()
+    }
+}
+
+impl Slot {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl Deref for Slot {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buf
+    }
+}
+
+impl DerefMut for Slot {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut buf = std::mem::take(&mut self.buf);
+
+        buf.clear();
+        let _ = self.tx.try_send((self.index, buf));
+    }
+}
+
+impl fmt::Debug for Slot {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Slot")
+            .field("index", &self.index)
+            .field("len", &self.buf.len())
+            .finish()
+    }
+}
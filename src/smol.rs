@@ -0,0 +1,27 @@
+use crate::{core::ThreadPool, error::JobError, handle, job::JobBox};
+use async_trait::async_trait;
+use executor_trait::BlockingExecutor;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Serves as the blocking pool of libraries written against
+/// `executor_trait`, as smol-based ones are. A closure the pool refuses runs
+/// on the awaiting task instead, and a panic in it resumes there.
+#[async_trait]
+impl BlockingExecutor for ThreadPool<Box<dyn JobBox>> {
+    async fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send + 'static>) {
+        let (job, handle) = handle::wrap(f);
+
+        if let Err(err) = self.send_fn(job) {
+            let job = err.into_inner();
+
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| job.call_box()));
+        }
+
+        match handle.await {
+            Ok(()) => {}
+            Err(JobError::Panicked(message)) => panic::resume_unwind(Box::new(message)),
+            Err(JobError::Cancelled) => panic!("blocking job cancelled by the pool"),
+            Err(JobError::Taken) => unreachable!("result of an awaited job taken"),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{lifecycle::{Lifecycle, LIFECYCLE_BITS, LIFECYCLE_MASK}};
+use crate::lifecycle::{Lifecycle, LIFECYCLE_BITS, LIFECYCLE_MASK};
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct State {
@@ -7,14 +7,12 @@ pub struct State {
 
 impl State {
     pub fn load(num: usize) -> State {
-        State {
-            state: num
-        }
+        State { state: num }
     }
 
     pub fn of(lifecycle: Lifecycle) -> State {
         State {
-            state: lifecycle as usize
+            state: lifecycle as usize,
         }
     }
 
@@ -24,9 +22,7 @@ impl State {
 
     pub fn with_lifecycle(&self, lifecycle: Lifecycle) -> State {
         let state = self.state & !LIFECYCLE_MASK | lifecycle as usize;
-        State {
-            state: state
-        }
+        State { state }
     }
 
     pub fn worker_count(&self) -> usize {
@@ -37,6 +33,10 @@ impl State {
         self.lifecycle() == Lifecycle::Terminated
     }
 
+    pub fn is_shutdown(&self) -> bool {
+        self.lifecycle() >= Lifecycle::Shutdown
+    }
+
     pub fn is_stoped(&self) -> bool {
         self.lifecycle() >= Lifecycle::Stop
     }
@@ -0,0 +1,178 @@
+#[cfg(feature = "alloc-stats")]
+use crate::alloc_stats::AllocStats;
+use crate::lifecycle::Lifecycle;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PoolStats {
+    pub lifecycle: Lifecycle,
+    pub size: usize,
+    pub workers: usize,
+    pub queued: usize,
+    /// Workers running a job.
+    pub active: usize,
+    /// Workers waiting for a job.
+    pub idle: usize,
+    /// Jobs accepted into a queue since the pool started.
+    pub submitted: u64,
+    /// Jobs that returned normally.
+    pub completed: u64,
+    pub panicked: u64,
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct KindStats {
+    pub completed: u64,
+    pub panicked: u64,
+    pub busy: Duration,
+    /// CPU time of the jobs, if `TPBuilder::cpu_time` is set and the platform
+    /// supports it. Much less than `busy` means they mostly waited on IO.
+    pub cpu: Duration,
+    /// Jobs that finished after their deadline.
+    pub deadline_misses: u64,
+    /// How long jobs of this kind waited in the queue.
+    pub residency: Histogram,
+    #[cfg(feature = "alloc-stats")]
+    pub allocated: AllocStats,
+}
+
+/// One worker's share of the work, see `ThreadPool::dump`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct WorkerStats {
+    pub index: usize,
+    pub thread: Option<String>,
+    /// Jobs that returned normally.
+    pub completed: u64,
+    pub panicked: u64,
+    pub busy: Duration,
+    /// Id of the job the worker is running.
+    pub running: Option<u64>,
+}
+
+/// Snapshot of a pool returned by `ThreadPool::dump`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PoolDump {
+    pub stats: PoolStats,
+    /// Live workers, by index.
+    pub workers: Vec<WorkerStats>,
+    pub kinds: HashMap<&'static str, KindStats>,
+}
+
+// Counters a worker updates as it runs jobs.
+#[derive(Debug)]
+pub struct WorkerCounters {
+    index: usize,
+    thread: Option<String>,
+    completed: AtomicU64,
+    panicked: AtomicU64,
+    busy: AtomicU64,
+    // Id of the running job plus one, or zero.
+    running: AtomicU64,
+}
+
+impl WorkerCounters {
+    pub fn new(index: usize, thread: Option<String>) -> WorkerCounters {
+        WorkerCounters {
+            index,
+            thread,
+            completed: AtomicU64::new(0),
+            panicked: AtomicU64::new(0),
+            busy: AtomicU64::new(0),
+            running: AtomicU64::new(0),
+        }
+    }
+
+    /// Marks job `id` as running and returns what to pass to `finish`.
+    pub fn start(&self, id: u64) -> u64 {
+        self.running.swap(id + 1, Ordering::SeqCst)
+    }
+
+    pub fn finish(&self, prev: u64, busy: Duration, panicked: bool) {
+        let count = if panicked {
+            &self.panicked
+        } else {
+            &self.completed
+        };
+
+        count.fetch_add(1, Ordering::SeqCst);
+        self.busy
+            .fetch_add(busy.as_nanos() as u64, Ordering::SeqCst);
+        self.running.store(prev, Ordering::SeqCst);
+    }
+
+    pub fn snapshot(&self) -> WorkerStats {
+        WorkerStats {
+            index: self.index,
+            thread: self.thread.clone(),
+            completed: self.completed.load(Ordering::SeqCst),
+            panicked: self.panicked.load(Ordering::SeqCst),
+            busy: Duration::from_nanos(self.busy.load(Ordering::SeqCst)),
+            running: self.running.load(Ordering::SeqCst).checked_sub(1),
+        }
+    }
+}
+
+const BUCKETS: usize = 24;
+
+/// Counts durations in power-of-two buckets of microseconds: bucket 0 holds
+/// everything under 1µs, bucket `i` everything under `2^i` µs, and the last
+/// bucket everything longer.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Histogram {
+    pub counts: [u64; BUCKETS],
+}
+
+impl Histogram {
+    pub fn record(&mut self, duration: Duration) {
+        let micros = duration.as_micros();
+        let bucket = (128 - micros.leading_zeros()) as usize;
+
+        self.counts[bucket.min(BUCKETS - 1)] += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Upper bound of bucket `i`; `None` for the last, open-ended one.
+    pub fn bound(i: usize) -> Option<Duration> {
+        if i + 1 < BUCKETS {
+            Some(Duration::from_micros(1 << i))
+        } else {
+            None
+        }
+    }
+
+    /// Upper bound of the bucket holding the `q` quantile, between 0 and 1.
+    /// `None` if nothing was recorded or it falls in the last bucket.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+
+        for (i, n) in self.counts.iter().enumerate() {
+            seen += n;
+
+            if seen >= rank {
+                return Histogram::bound(i);
+            }
+        }
+
+        None
+    }
+}
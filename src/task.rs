@@ -0,0 +1,59 @@
+use crate::context::JobContext;
+use crate::registry::Tracker;
+use crate::sender::Lease;
+use std::time::Instant;
+
+pub struct Task<T> {
+    pub id: u64,
+    pub job: T,
+    pub name: Option<String>,
+    pub context: Option<JobContext>,
+    pub report: bool,
+    /// Sent with `send_critical`: never dropped without running.
+    pub critical: bool,
+    pub lease: Option<Lease>,
+    pub queued_at: Instant,
+    pub deadline: Option<Instant>,
+    pub tracker: Option<Tracker>,
+    /// The span current when the job was sent, entered while it runs. Boxed
+    /// to keep tasks small, which matters as they are moved around on send.
+    #[cfg(feature = "tracing")]
+    pub span: Option<Box<tracing::Span>>,
+}
+
+impl<T> Task<T> {
+    pub fn new(id: u64, job: T) -> Task<T> {
+        Task {
+            id,
+            job,
+            name: None,
+            context: None,
+            report: false,
+            critical: false,
+            lease: None,
+            queued_at: Instant::now(),
+            deadline: None,
+            tracker: None,
+            #[cfg(feature = "tracing")]
+            span: current_span(),
+        }
+    }
+
+    /// Called as the task goes into a queue, see `Tracker::queued`.
+    pub fn queued(&mut self) {
+        if let Some(tracker) = self.tracker.as_mut() {
+            tracker.queued();
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn current_span() -> Option<Box<tracing::Span>> {
+    let span = tracing::Span::current();
+
+    if span.is_none() {
+        None
+    } else {
+        Some(Box::new(span))
+    }
+}
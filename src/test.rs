@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+use crate::error::{JobError, SubmitError};
+use crate::handle::{self, JobHandle};
+use crate::job::{Executor, Job, JobBox};
+use crossbeam_channel::SendError;
+
+/// Stand-in for `ThreadPool` in tests. Nothing runs until the test calls
+/// `step` or `run_until_idle`, which run queued jobs in order on the calling
+/// thread, so interleavings are deterministic without sleeps.
+pub struct StepPool<T> {
+    shared: Arc<Shared<T>>,
+}
+
+struct Shared<T> {
+    queue: Mutex<VecDeque<T>>,
+    closed: Mutex<bool>,
+}
+
+impl<T: Job> StepPool<T> {
+    pub fn new() -> StepPool<T> {
+        StepPool {
+            shared: Arc::new(Shared {
+                queue: Mutex::new(VecDeque::new()),
+                closed: Mutex::new(false),
+            }),
+        }
+    }
+
+    pub fn send(&self, job: T) -> Result<(), SubmitError<T>> {
+        if *self.shared.closed.lock().unwrap() {
+            return Err(SubmitError::ShuttingDown(job));
+        }
+
+        self.shared.queue.lock().unwrap().push_back(job);
+
+        Ok(())
+    }
+
+    /// The queue is unbounded, so this only fails once the pool is closed.
+    pub fn try_send(&self, job: T) -> Result<(), SubmitError<T>> {
+        self.send(job)
+    }
+
+    /// Refuses further jobs. Queued ones still run on the next steps.
+    pub fn close(&self) {
+        *self.shared.closed.lock().unwrap() = true;
+    }
+
+    pub fn queued(&self) -> usize {
+        self.shared.queue.lock().unwrap().len()
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        *self.shared.closed.lock().unwrap() && self.queued() == 0
+    }
+
+    /// Runs the oldest queued job, or returns `None` if there is none. A
+    /// panic is caught and returned like a worker would report it.
+    pub fn step(&self) -> Option<Result<(), JobError>> {
+        // Popped before running so the job can send more jobs.
+        let job = self.shared.queue.lock().unwrap().pop_front()?;
+
+        Some(
+            panic::catch_unwind(AssertUnwindSafe(move || job.call()))
+                .map_err(|payload| JobError::from_panic(&*payload)),
+        )
+    }
+
+    /// Steps until the queue is empty, including jobs queued by the jobs it
+    /// runs, and returns how many ran.
+    pub fn run_until_idle(&self) -> usize {
+        let mut ran = 0;
+
+        while self.step().is_some() {
+            ran += 1;
+        }
+
+        ran
+    }
+}
+
+impl StepPool<Box<dyn JobBox>> {
+    pub fn send_fn<F>(&self, job: F) -> Result<(), SubmitError<Box<dyn JobBox>>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.send(Box::new(job))
+    }
+
+    /// Queues `f` and returns a handle to its return value. If the pool is
+    /// closed the handle resolves to `JobError::Cancelled`.
+    pub fn submit<F, R>(&self, f: F) -> JobHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (job, handle) = handle::wrap(f);
+        let _ = self.send_fn(job);
+
+        handle
+    }
+}
+
+impl<T: Job> Executor<T> for StepPool<T> {
+    fn execute(&self, job: T) -> Result<(), SendError<T>> {
+        self.send(job).map_err(|err| SendError(err.into_inner()))
+    }
+}
+
+impl<T: Job> Default for StepPool<T> {
+    fn default() -> StepPool<T> {
+        StepPool::new()
+    }
+}
+
+impl<T> Clone for StepPool<T> {
+    fn clone(&self) -> Self {
+        StepPool {
+            shared: self.shared.clone(),
+        }
+    }
+}
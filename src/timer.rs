@@ -0,0 +1,270 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::Elapsed;
+
+pub struct Timer {
+    shared: Arc<Shared>,
+    name: Option<String>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    entries: BinaryHeap<Reverse<Entry>>,
+    next_seq: u64,
+    started: bool,
+    dropped: bool,
+}
+
+struct Entry {
+    deadline: Instant,
+    seq: u64,
+    waker: Arc<Mutex<Waker>>,
+}
+
+impl Timer {
+    pub fn new(name: Option<String>) -> Timer {
+        Timer {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State::default()),
+                changed: Condvar::new(),
+            }),
+            name,
+        }
+    }
+
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep {
+            shared: self.shared.clone(),
+            deadline: Instant::now() + duration,
+            name: self.name.clone(),
+            waker: None,
+        }
+    }
+
+    /// Wakes `waker` from the timer thread once `deadline` has passed.
+    pub fn wake_at(&self, deadline: Instant, waker: Waker) {
+        self.shared
+            .register(deadline, Arc::new(Mutex::new(waker)), &self.name);
+    }
+}
+
+/// Holds the timer's lock, so its thread cannot hold it while the process
+/// forks; see `ThreadPool::prepare_fork`.
+pub struct TimerLock<'a>(MutexGuard<'a, State>);
+
+impl Timer {
+    pub fn lock(&self) -> TimerLock<'_> {
+        TimerLock(self.shared.state.lock().unwrap())
+    }
+
+    /// Restarts the timer thread in a forked child, where it is gone.
+    pub fn reinit_after_fork(&self, lock: TimerLock<'_>) {
+        let mut state = lock.0;
+
+        state.started = false;
+        if !state.entries.is_empty() {
+            self.shared.start(&mut state, &self.name);
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().dropped = true;
+        self.shared.changed.notify_one();
+    }
+}
+
+impl Shared {
+    fn register(
+        self: &Arc<Self>,
+        deadline: Instant,
+        waker: Arc<Mutex<Waker>>,
+        name: &Option<String>,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let seq = state.next_seq;
+
+        state.next_seq += 1;
+        state.entries.push(Reverse(Entry {
+            deadline,
+            seq,
+            waker,
+        }));
+
+        if !state.started {
+            self.start(&mut state, name);
+        }
+
+        self.changed.notify_one();
+    }
+
+    fn start(self: &Arc<Self>, state: &mut State, name: &Option<String>) {
+        state.started = true;
+
+        let shared = self.clone();
+        let name = match *name {
+            Some(ref name) => format!("{}-timer", name),
+            None => "multix-timer".to_string(),
+        };
+
+        thread::Builder::new()
+            .name(name)
+            .spawn(move || shared.run())
+            .expect("failed to spawn timer thread");
+    }
+
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        while !state.dropped {
+            let now = Instant::now();
+            let mut due = Vec::new();
+
+            while state
+                .entries
+                .peek()
+                .is_some_and(|entry| entry.0.deadline <= now)
+            {
+                due.push(state.entries.pop().unwrap().0.waker);
+            }
+
+            // Woken without the lock, since a waker may register again.
+            if !due.is_empty() {
+                drop(state);
+                for waker in due {
+                    waker.lock().unwrap().wake_by_ref();
+                }
+                state = self.state.lock().unwrap();
+                continue;
+            }
+
+            state = match state.entries.peek() {
+                Some(entry) => {
+                    let wait = entry.0.deadline - now;
+                    self.changed.wait_timeout(state, wait).unwrap().0
+                }
+                None => self.changed.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Entry) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Entry) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Entry) -> Ordering {
+        (self.deadline, self.seq).cmp(&(other.deadline, other.seq))
+    }
+}
+
+/// Future returned by `ThreadPool::sleep`.
+pub struct Sleep {
+    shared: Arc<Shared>,
+    deadline: Instant,
+    name: Option<String>,
+    // The waker of its timer entry, registered on the first pending poll.
+    waker: Option<Arc<Mutex<Waker>>>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+
+        match self.waker {
+            Some(ref waker) => {
+                let mut waker = waker.lock().unwrap();
+
+                if !waker.will_wake(cx.waker()) {
+                    *waker = cx.waker().clone();
+                }
+            }
+            None => {
+                let waker = Arc::new(Mutex::new(cx.waker().clone()));
+
+                self.shared
+                    .register(self.deadline, waker.clone(), &self.name);
+                self.waker = Some(waker);
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Cancels a recurrence started with `ThreadPool::send_repeating`. Dropping
+/// the handle leaves the recurrence running.
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ScheduleHandle {
+    /// Stops queueing new jobs. A job already queued still runs.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, AtomicOrdering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(AtomicOrdering::SeqCst)
+    }
+}
+
+/// Future returned by `ThreadPool::timeout`.
+pub struct Timeout<F> {
+    future: Pin<Box<F>>,
+    sleep: Sleep,
+}
+
+impl<F> Timeout<F> {
+    pub fn new(future: F, sleep: Sleep) -> Timeout<F> {
+        Timeout {
+            future: Box::pin(future),
+            sleep,
+        }
+    }
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+
+        match Pin::new(&mut self.sleep).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(Elapsed)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::fs;
+
+use crate::affinity::{self, CoreKind};
+
+/// Layout of the machine's CPUs, for placement decisions. Detected from
+/// sysfs on Linux; elsewhere every CPU is reported as a core of its own.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Topology {
+    pub cpus: Vec<Cpu>,
+    /// Groups of CPUs sharing a data or unified cache.
+    pub caches: Vec<CacheGroup>,
+    /// CPUs of each NUMA node, empty where nodes are unknown.
+    pub nodes: Vec<Vec<usize>>,
+}
+
+/// A logical CPU.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Cpu {
+    pub id: usize,
+    pub package: usize,
+    /// Physical core, unique within the package.
+    pub core: usize,
+    /// Logical CPUs sharing the physical core through SMT, this one included.
+    pub siblings: Vec<usize>,
+    pub node: Option<usize>,
+    /// `CoreKind::Any` unless the CPU is hybrid.
+    pub kind: CoreKind,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CacheGroup {
+    pub level: u8,
+    pub size: Option<u64>,
+    pub cpus: Vec<usize>,
+}
+
+impl Topology {
+    pub fn detect() -> Topology {
+        detect().unwrap_or_else(|| Topology {
+            cpus: (0..num_cpus::get())
+                .map(|id| Cpu {
+                    id,
+                    package: 0,
+                    core: id,
+                    siblings: vec![id],
+                    node: None,
+                    kind: CoreKind::Any,
+                })
+                .collect(),
+            caches: Vec::new(),
+            nodes: Vec::new(),
+        })
+    }
+
+    pub fn physical_cores(&self) -> usize {
+        let mut cores: Vec<_> = self
+            .cpus
+            .iter()
+            .map(|cpu| (cpu.package, cpu.core))
+            .collect();
+        cores.sort_unstable();
+        cores.dedup();
+
+        cores.len()
+    }
+
+    pub fn node_of(&self, cpu: usize) -> Option<usize> {
+        self.cpus.iter().find(|c| c.id == cpu)?.node
+    }
+
+    /// Every CPU ordered so that each physical core comes up once before any
+    /// of their SMT siblings do, so workers pinned in this order share cores
+    /// only once all of them are taken.
+    pub fn spread(&self) -> Vec<usize> {
+        let mut cpus: Vec<_> = self
+            .cpus
+            .iter()
+            .map(|cpu| {
+                let rank = cpu.siblings.iter().filter(|&&s| s < cpu.id).count();
+                (rank, cpu.id)
+            })
+            .collect();
+        cpus.sort_unstable();
+
+        cpus.into_iter().map(|(_, id)| id).collect()
+    }
+}
+
+// The nodes of a pool in NUMA mode, see `TPBuilder::numa`.
+pub(crate) struct Numa {
+    pub nodes: Vec<Vec<usize>>,
+    // Node of every CPU, indexed by CPU.
+    node_of: Vec<Option<usize>>,
+}
+
+impl Numa {
+    pub fn detect() -> Option<Numa> {
+        let nodes: Vec<_> = nodes()
+            .into_iter()
+            .filter(|cpus| !cpus.is_empty())
+            .collect();
+
+        if nodes.is_empty() {
+            return None;
+        }
+
+        let len = nodes.iter().flatten().max().map_or(0, |&cpu| cpu + 1);
+        let mut node_of = vec![None; len];
+        for (node, cpus) in nodes.iter().enumerate() {
+            for &cpu in cpus {
+                node_of[cpu] = Some(node);
+            }
+        }
+
+        Some(Numa { nodes, node_of })
+    }
+
+    /// Node of the CPU the calling thread runs on.
+    pub fn current_node(&self) -> Option<usize> {
+        *self.node_of.get(affinity::current_cpu()?)?
+    }
+}
+
+fn detect() -> Option<Topology> {
+    const CPU: &str = "/sys/devices/system/cpu";
+
+    let online = affinity::parse_list(&fs::read_to_string(format!("{}/online", CPU)).ok()?)?;
+    let nodes = nodes();
+    let performance = affinity::cores_of(CoreKind::Performance);
+    let mut cpus = Vec::with_capacity(online.len());
+    let mut caches: Vec<CacheGroup> = Vec::new();
+
+    for id in online {
+        let dir = format!("{}/cpu{}", CPU, id);
+        let kind = match performance.as_ref() {
+            Some(performance) if performance.contains(&id) => CoreKind::Performance,
+            Some(_) => CoreKind::Efficiency,
+            None => CoreKind::Any,
+        };
+
+        cpus.push(Cpu {
+            id,
+            package: read(&format!("{}/topology/physical_package_id", dir)).unwrap_or(0),
+            core: read(&format!("{}/topology/core_id", dir)).unwrap_or(id),
+            siblings: read_list(&format!("{}/topology/thread_siblings_list", dir))
+                .unwrap_or_else(|| vec![id]),
+            node: nodes.iter().position(|node| node.contains(&id)),
+            kind,
+        });
+
+        for index in 0.. {
+            let cache = format!("{}/cache/index{}", dir, index);
+            let kind = match fs::read_to_string(format!("{}/type", cache)) {
+                Ok(kind) => kind,
+                Err(_) => break,
+            };
+
+            if kind.trim() == "Instruction" {
+                continue;
+            }
+
+            let group = CacheGroup {
+                level: read(&format!("{}/level", cache)).unwrap_or(0),
+                size: fs::read_to_string(format!("{}/size", cache))
+                    .ok()
+                    .and_then(|size| parse_size(&size)),
+                cpus: read_list(&format!("{}/shared_cpu_list", cache)).unwrap_or_default(),
+            };
+
+            if !caches.contains(&group) {
+                caches.push(group);
+            }
+        }
+    }
+
+    Some(Topology {
+        cpus,
+        caches,
+        nodes,
+    })
+}
+
+fn nodes() -> Vec<Vec<usize>> {
+    let mut nodes = Vec::new();
+
+    for node in 0.. {
+        match read_list(&format!("/sys/devices/system/node/node{}/cpulist", node)) {
+            Some(cpus) => nodes.push(cpus),
+            None => break,
+        }
+    }
+
+    nodes
+}
+
+fn read<N: std::str::FromStr>(path: &str) -> Option<N> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn read_list(path: &str) -> Option<Vec<usize>> {
+    affinity::parse_list(&fs::read_to_string(path).ok()?)
+}
+
+// Cache sizes read like `32K` or `8M`.
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let (digits, unit) = match size.find(|c: char| !c.is_ascii_digit()) {
+        Some(at) => size.split_at(at),
+        None => (size, ""),
+    };
+    let shift = match unit {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        _ => return None,
+    };
+
+    Some(digits.parse::<u64>().ok()? << shift)
+}
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+use std::task::{Wake, Waker};
+
+use crate::{core::ThreadPool, job::Job};
+
+struct PoolWaker<T, F> {
+    pool: ThreadPool<T>,
+    factory: F,
+}
+
+impl<T, F> Wake for PoolWaker<T, F>
+where
+    T: Job,
+    F: Fn() -> T + Send + Sync + 'static,
+{
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let _ = self.pool.send((self.factory)());
+    }
+}
+
+/// Returns a waker that submits a job built by `factory` to `pool` each time
+/// it is woken. Wakeups after the pool is closed are ignored.
+pub fn waker_for<T, F>(pool: &ThreadPool<T>, factory: F) -> Waker
+where
+    T: Job,
+    F: Fn() -> T + Send + Sync + 'static,
+{
+    Arc::new(PoolWaker {
+        pool: pool.clone(),
+        factory,
+    })
+    .into()
+}
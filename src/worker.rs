@@ -1,52 +1,567 @@
 use self::core::Inner;
-use crate::{core, job};
+use crate::{
+    affinity, context, core, cpu, error, idle, job, lifecycle, outcome, priority, rng, scratch,
+    stats, task,
+};
+use affinity::Pinning;
+use context::JobContext;
 use crossbeam_channel::{Receiver, RecvTimeoutError, TryRecvError};
-use job::Job;
+use error::JobError;
+use idle::Idle;
+use job::{Job, Next};
+use lifecycle::Lifecycle;
+use outcome::{DeadlineMiss, JobMeta, JobOutcome, JobTimestamps};
+use priority::{Lanes, Priority, Slot};
+use stats::WorkerCounters;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use task::Task;
+
+thread_local! {
+    // Address of the `Inner` of the pool the current thread works for.
+    static POOL: Cell<usize> = const { Cell::new(0) };
+    static INDEX: Cell<usize> = const { Cell::new(0) };
+    // The worker's LIFO slot and the lanes it is registered with, a `Lifo`
+    // of its pool's job type.
+    static LIFO: RefCell<Option<Box<dyn Any>>> = const { RefCell::new(None) };
+    // Whether the calling worker counts in its pool's `Inner::fork`.
+    static ENTERED: Cell<bool> = const { Cell::new(false) };
+    static COUNTERS: RefCell<Option<Arc<WorkerCounters>>> = const { RefCell::new(None) };
+}
+
+// Lists a worker in `Inner::workers` until it exits, however it exits.
+struct Registered {
+    inner: Arc<Inner>,
+    counters: Arc<WorkerCounters>,
+}
+
+impl Registered {
+    fn new(inner: &Arc<Inner>, index: usize) -> Registered {
+        let thread = thread::current().name().map(String::from);
+        let counters = Arc::new(WorkerCounters::new(index, thread));
+
+        inner.workers.lock().unwrap().push(counters.clone());
+        COUNTERS.with(|c| *c.borrow_mut() = Some(counters.clone()));
+
+        Registered {
+            inner: inner.clone(),
+            counters,
+        }
+    }
+}
+
+impl Drop for Registered {
+    fn drop(&mut self) {
+        COUNTERS.with(|c| c.borrow_mut().take());
+        if let Ok(mut workers) = self.inner.workers.lock() {
+            workers.retain(|c| !Arc::ptr_eq(c, &self.counters));
+        }
+    }
+}
+
+/// Whether the calling thread is a worker of the pool owning `inner`.
+pub fn is_worker_of(inner: &Arc<Inner>) -> bool {
+    POOL.with(|pool| pool.get() == Arc::as_ptr(inner) as usize)
+}
+
+/// Puts `task` in the calling worker's LIFO slot, the next job it runs, and
+/// returns the task that was there. Only for workers of `task`'s pool.
+pub fn replace_lifo<T: Job>(task: Task<T>) -> Option<Task<T>> {
+    LIFO.with(
+        |lifo| match lifo.borrow().as_ref().and_then(|lifo| lifo.downcast_ref()) {
+            Some(Lifo::<T> { lanes, slot }) => lanes.replace_slot(slot, task),
+            None => Some(task),
+        },
+    )
+}
+
+fn take_lifo<T: Job>() -> Option<Task<T>> {
+    LIFO.with(|lifo| {
+        let lifo = lifo.borrow();
+        let Lifo::<T> { lanes, slot } = lifo.as_ref()?.downcast_ref()?;
+
+        lanes.take_slot(slot)
+    })
+}
+
+// Counts the calling worker in `Inner::fork` while it runs a job.
+struct Running<'a> {
+    inner: &'a Inner,
+    prev: bool,
+}
+
+impl Running<'_> {
+    fn enter(inner: &Inner) -> Running<'_> {
+        inner.fork.enter();
+
+        Running {
+            inner,
+            prev: ENTERED.with(|entered| entered.replace(true)),
+        }
+    }
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.inner.fork.leave();
+        ENTERED.with(|entered| entered.set(self.prev));
+    }
+}
+
+/// Takes the calling worker's job out of `Inner::fork` until dropped, e.g.
+/// while it waits on jobs a pending fork holds back.
+pub struct Paused {
+    inner: Arc<Inner>,
+}
+
+impl Paused {
+    pub fn new(inner: &Arc<Inner>) -> Option<Paused> {
+        if !is_worker_of(inner) || !ENTERED.with(|entered| entered.replace(false)) {
+            return None;
+        }
+
+        inner.fork.leave();
+
+        Some(Paused {
+            inner: inner.clone(),
+        })
+    }
+}
+
+impl Drop for Paused {
+    fn drop(&mut self) {
+        self.inner.fork.enter();
+        ENTERED.with(|entered| entered.set(true));
+    }
+}
+
+// Installs the worker's LIFO slot until it exits. A job still in it then is
+// moved to the `High` lane, it was meant to run next.
+struct Lifo<T> {
+    lanes: Arc<Lanes<T>>,
+    slot: Arc<Slot<T>>,
+}
+
+impl<T: Job> Lifo<T> {
+    fn install(lanes: &Arc<Lanes<T>>) -> LifoGuard<T> {
+        let lifo = Lifo {
+            lanes: lanes.clone(),
+            slot: lanes.add_slot(),
+        };
+        LIFO.with(|l| *l.borrow_mut() = Some(Box::new(lifo)));
+
+        LifoGuard(PhantomData)
+    }
+}
+
+struct LifoGuard<T: Job>(PhantomData<T>);
+
+impl<T: Job> Drop for LifoGuard<T> {
+    fn drop(&mut self) {
+        let lifo = LIFO.with(|l| l.borrow_mut().take());
+
+        if let Some(Ok(lifo)) = lifo.map(|lifo| lifo.downcast::<Lifo<T>>()) {
+            if let Some(task) = lifo.lanes.remove_slot(&lifo.slot) {
+                lifo.lanes.push(Priority::High, task);
+            }
+        }
+    }
+}
+
+/// Runs a queued job of the pool owning `inner` if the calling thread is one
+/// of its workers, e.g. one waiting for jobs it sent. Returns whether it did.
+pub fn run_queued<T: Job>(
+    rx: &Receiver<Task<T>>,
+    lanes: &Arc<Lanes<T>>,
+    inner: &Arc<Inner>,
+) -> bool {
+    let index = match current_index(inner) {
+        Some(index) => index,
+        None => return false,
+    };
+    let worker = Worker {
+        index,
+        rx: rx.clone(),
+        lanes: lanes.clone(),
+        inner: inner.clone(),
+        dedicated: false,
+    };
+
+    match worker.try_recv() {
+        Ok(task) => {
+            let _running = Running::enter(inner);
+            worker.execute(task, &mut false);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Runs `task` on the calling thread the way a worker of the pool owning
+/// `inner` would, with its hooks, panic handling and stats. Outside the pool
+/// the job is reported as run by worker `usize::MAX`.
+pub fn run_inline<T: Job>(
+    task: Task<T>,
+    rx: &Receiver<Task<T>>,
+    lanes: &Arc<Lanes<T>>,
+    inner: &Arc<Inner>,
+) {
+    let worker = Worker {
+        index: current_index(inner).unwrap_or(usize::MAX),
+        rx: rx.clone(),
+        lanes: lanes.clone(),
+        inner: inner.clone(),
+        dedicated: false,
+    };
+
+    worker.execute(task, &mut false);
+}
+
+/// Index of the calling thread if it is a worker of the pool owning `inner`.
+pub fn current_index(inner: &Arc<Inner>) -> Option<usize> {
+    is_worker_of(inner).then(|| INDEX.with(Cell::get))
+}
 
 pub struct Worker<T> {
-    pub rx: Receiver<T>,
+    pub index: usize,
+    pub rx: Receiver<Task<T>>,
+    pub lanes: Arc<Lanes<T>>,
     pub inner: Arc<Inner>,
+    pub dedicated: bool,
+}
+
+/// Identifies the worker running a per-worker hook.
+#[derive(Debug, Clone, Copy)]
+pub struct WorkerContext<'a> {
+    index: usize,
+    pool_name: Option<&'a str>,
+}
+
+impl<'a> WorkerContext<'a> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn pool_name(&self) -> Option<&'a str> {
+        self.pool_name
+    }
 }
 
 impl<T: Job> Worker<T> {
-    pub fn spawn(self, initial_job: Option<T>) {
+    pub fn spawn(self, initial_job: Option<Task<T>>) {
         let mut b = thread::Builder::new();
 
         {
             let c = &self.inner.config;
 
-            if let Some(stack_size) = c.stack_size {
+            if let Some(stack_size) = c.thread_stack_size() {
                 b = b.stack_size(stack_size);
             }
+
+            if let Some(prefix) = c.name_prefix.as_ref().or(c.name.as_ref()) {
+                b = b.name(format!("{}-{}", prefix, self.index));
+            }
         }
 
-        b.spawn(move || self.run(initial_job)).unwrap();
+        b.spawn(move || {
+            use std::panic::{self, AssertUnwindSafe};
+
+            let inner = self.inner.clone();
+
+            // The sentinel has already replaced the worker; a panic outside a
+            // job only needs reporting.
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| self.run(initial_job))) {
+                if let Some(handler) = inner.config.panic_handler.as_ref() {
+                    handler(payload);
+                }
+            }
+        })
+        .unwrap();
     }
 
-    fn run(mut self, mut initial_job: Option<T>) {
-        use std::panic::{self, AssertUnwindSafe};
+    fn run(mut self, mut initial_job: Option<Task<T>>) {
+        if let Some(cpus) = self.inner.cpus.as_ref() {
+            match self.inner.config.pinning {
+                Pinning::Unpinned => affinity::pin_current(cpus),
+                _ => affinity::pin_current(&[cpus[self.index % cpus.len()]]),
+            };
+        } else {
+            #[cfg(feature = "topology")]
+            if let Some(numa) = self.inner.numa.as_ref() {
+                affinity::pin_current(&numa.nodes[self.index % numa.nodes.len()]);
+            }
+        }
+
+        if let Some(init) = self.inner.config.scratch.as_ref() {
+            scratch::install(init(&self.context()));
+        }
+
+        if let Some(seed) = self.inner.config.seed_rng.as_ref() {
+            rng::install(seed(self.index));
+        }
+
+        #[cfg(feature = "arena")]
+        {
+            if let Some(capacity) = self.inner.config.arena {
+                crate::arena::install(capacity);
+            }
+        }
+
+        let mut sentinel = Sentinel {
+            rx: self.rx.clone(),
+            lanes: self.lanes.clone(),
+            inner: self.inner.clone(),
+            dedicated: self.dedicated,
+            busy: false,
+        };
 
-        self.inner.config.mount.as_ref().map(|f| f());
+        POOL.with(|pool| pool.set(Arc::as_ptr(&self.inner) as usize));
+        INDEX.with(|index| index.set(self.index));
+        let _registered = Registered::new(&self.inner, self.index);
+        let _lifo = Lifo::install(&self.lanes);
+        self.inner.mount(&self.context());
 
-        while let Some(job) = self.next_job(initial_job.take()) {
-            let _ = panic::catch_unwind(AssertUnwindSafe(move || job.call()));
+        while let Some(task) = self.next_job(initial_job.take()) {
+            let _running = Running::enter(&self.inner);
+
+            self.execute(task, &mut sentinel.busy);
         }
     }
 
-    fn next_job(&mut self, mut job: Option<T>) -> Option<T> {
-        let state = self.inner.state.load();
+    // Runs `task`, with `busy` set while it does.
+    fn execute(&self, task: Task<T>, busy: &mut bool) {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let dequeued = Instant::now();
+        let Task {
+            id,
+            job,
+            name,
+            context,
+            report,
+            lease,
+            queued_at,
+            deadline,
+            critical: _,
+            mut tracker,
+            #[cfg(feature = "tracing")]
+            span,
+        } = task;
+        #[cfg(feature = "tracing")]
+        let _span = span.map(|span| span.entered());
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            target: "multix",
+            job = id,
+            wait = ?dequeued.duration_since(queued_at),
+            "job dequeued"
+        );
+
+        #[cfg(feature = "fault-injection")]
+        let inject_panic = match self.inner.faults.as_ref() {
+            Some(faults) => {
+                if let Some(delay) = faults.delay() {
+                    thread::sleep(delay);
+                }
+
+                faults.panic_job()
+            }
+            None => false,
+        };
+
+        if let Some(tracker) = tracker.as_mut() {
+            tracker.start();
+        }
+        self.inner.active.fetch_add(1, Ordering::SeqCst);
+        *busy = true;
+        let prev = JobContext::enter(context);
+        let guard = self.inner.config.guard.as_ref();
+        if let Some(guard) = guard {
+            guard.enter();
+        }
+        let kind = job.kind();
+        let meta = JobMeta {
+            id,
+            name: name.as_deref(),
+            kind,
+            worker: self.index,
+        };
+        if let Some(f) = self.inner.config.before_job.as_ref() {
+            f(&meta);
+        }
+        #[cfg(feature = "metrics")]
+        crate::instrument::started(
+            &self.inner,
+            self.rx.len() + self.lanes.len(),
+            self.inner.active.load(Ordering::SeqCst),
+            queued_at.elapsed(),
+        );
+        #[cfg(feature = "alloc-stats")]
+        let allocs = crate::alloc_stats::current();
+        let cpu_started = self.inner.config.cpu_time.then(cpu::thread_time).flatten();
+        let counters = COUNTERS.with(|c| c.borrow().clone());
+        let running = counters.as_ref().map(|c| c.start(id));
+        let started = Instant::now();
+        let residency = started.duration_since(queued_at);
+        let handler = self.inner.config.handler.as_ref();
+        let worker = self.context();
+        let run = move || {
+            #[cfg(feature = "fault-injection")]
+            if inject_panic {
+                drop(job);
+                panic!("injected fault");
+            }
+
+            job.call();
+            if let Some(handler) = handler {
+                handler(&worker);
+            }
+        };
+        let layers = &self.inner.config.layers;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            if layers.is_empty() {
+                return run();
+            }
+
+            let mut run = Some(run);
+            Next::new(layers, &meta, &mut || {
+                if let Some(run) = run.take() {
+                    run();
+                }
+            })
+            .run();
+        }));
+        let duration = started.elapsed();
+        if let (Some(counters), Some(running)) = (counters, running) {
+            counters.finish(running, duration, result.is_err());
+        }
+        let cpu = cpu_started.and_then(|start| Some(cpu::thread_time()? - start));
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            target: "multix",
+            job = id,
+            run = ?duration,
+            panicked = result.is_err(),
+            "job completed"
+        );
+        let late = deadline.and_then(|deadline| Instant::now().checked_duration_since(deadline));
+        #[cfg(feature = "alloc-stats")]
+        let allocated = crate::alloc_stats::current().since(allocs);
+        let result = result.map_err(|payload| {
+            let err = JobError::from_panic(&*payload);
+
+            if let Some(handler) = self.inner.config.panic_handler.as_ref() {
+                handler(payload);
+            }
+
+            err
+        });
+        if let Some(f) = self.inner.config.after_job.as_ref() {
+            f(&meta, duration);
+        }
+        if let Some(kind) = kind {
+            let mut kinds = self.inner.kinds.lock().unwrap();
+            let stats = kinds.entry(kind).or_default();
+
+            match result {
+                Ok(_) => stats.completed += 1,
+                Err(_) => stats.panicked += 1,
+            }
+            stats.busy += duration;
+            stats.cpu += cpu.unwrap_or_default();
+            stats.residency.record(residency);
+            if late.is_some() {
+                stats.deadline_misses += 1;
+            }
+            #[cfg(feature = "alloc-stats")]
+            {
+                stats.allocated.bytes += allocated.bytes;
+                stats.allocated.count += allocated.count;
+            }
+        }
+        match (guard, &result) {
+            (Some(guard), Ok(_)) => guard.exit(),
+            (Some(guard), Err(_)) => guard.recover(),
+            (None, _) => {}
+        }
+        JobContext::enter(prev);
+        #[cfg(feature = "arena")]
+        crate::arena::reset();
+        if result.is_err() {
+            self.inner.panicked.fetch_add(1, Ordering::SeqCst);
+        }
+        self.inner.completed.fetch_add(1, Ordering::SeqCst);
+        self.inner.active.fetch_sub(1, Ordering::SeqCst);
+        if self.inner.crashes.load(Ordering::Relaxed) != 0 {
+            self.inner.crashes.store(0, Ordering::Relaxed);
+        }
+        #[cfg(feature = "metrics")]
+        crate::instrument::finished(
+            &self.inner,
+            self.inner.active.load(Ordering::SeqCst),
+            duration,
+            result.is_err(),
+        );
+        *busy = false;
+        drop(lease);
+        if let Some(tracker) = tracker.as_mut() {
+            tracker.finish(&result);
+        }
+
+        if let Some(sink) = self.inner.config.timestamps.as_ref() {
+            sink(&JobTimestamps {
+                id,
+                kind,
+                enqueued: queued_at,
+                dequeued,
+                completed: started + duration,
+            });
+        }
+
+        if let Some(late) = late {
+            self.inner.deadline_misses.fetch_add(1, Ordering::SeqCst);
 
+            if let Some(f) = self.inner.config.on_deadline_miss.as_ref() {
+                f(&DeadlineMiss {
+                    id,
+                    name: name.clone(),
+                    kind,
+                    late,
+                });
+            }
+        }
+
+        if report {
+            let _ = self.inner.outcomes.0.send(JobOutcome {
+                id,
+                name,
+                duration,
+                cpu,
+                result,
+                #[cfg(feature = "alloc-stats")]
+                allocated,
+            });
+        }
+    }
+
+    fn next_job(&mut self, job: Option<Task<T>>) -> Option<Task<T>> {
+        let mut job = job;
         let mut timed_out = false;
-        let size = self.inner.config.size;
+        let idle_since = Instant::now();
+        let mut last_tick = idle_since;
 
         loop {
-            if state.is_stoped() {
-                self.inner.config.unmount.as_ref().map(|f| f());
+            let signal = self.inner.control.subscribe();
+            let state = self.inner.state.load();
 
-                self.decrement_worker_count();
+            // A critical job already taken still runs after `close_force`.
+            if state.is_stoped() && !job.as_ref().is_some_and(|task| task.critical) {
+                self.exit();
 
                 return None;
             }
@@ -55,39 +570,86 @@ impl<T: Job> Worker<T> {
                 break;
             }
 
+            // Before any way out, so the slot is empty when the worker exits.
+            if let Some(task) = take_lifo() {
+                job = Some(task);
+                break;
+            }
+
             let wc = state.worker_count();
 
-            let timeout = if wc > size {
-                self.inner.config.timeout
-            } else {
-                None
-            };
+            // The pool was shrunk, see `ThreadPool::resize`.
+            if !self.dedicated && wc > self.inner.worker_limit() {
+                if self.inner.state.compare_and_dec_worker_count(state) {
+                    self.inner.unmount(&self.context());
 
-            if timeout.is_some() && timed_out {
-                if wc > 1 || self.rx.len() == 0 {
-                    if self.inner.state.compare_and_dec_worker_count(state) {
-                        self.inner.config.unmount.as_ref().map(|f| f());
+                    return None;
+                }
+
+                continue;
+            }
+
+            if state.is_shutdown() {
+                match self.try_recv() {
+                    Ok(t) => {
+                        job = Some(t);
+                        continue;
+                    }
+                    Err(_) => {
+                        self.exit();
 
                         return None;
                     }
+                }
+            }
 
-                    continue;
+            let config = &self.inner.config;
+            let timeout =
+                if self.dedicated || (config.max_size.is_some() && wc <= self.inner.size()) {
+                    None
+                } else {
+                    config.timeout
+                };
+
+            if timeout.is_some()
+                && timed_out
+                && (wc > 1 || (self.rx.is_empty() && self.lanes.is_empty()))
+            {
+                if self.inner.state.compare_and_dec_worker_count(state) {
+                    self.inner.unmount(&self.context());
+
+                    return None;
                 }
+
+                continue;
             }
 
-            match self.recv_job(timeout) {
+            let tick = self.inner.config.idle_tick.as_ref();
+            let remaining = timeout.map(|timeout| timeout.saturating_sub(idle_since.elapsed()));
+            let wait = match (remaining, tick) {
+                (Some(remaining), Some(tick)) => Some(remaining.min(tick.0)),
+                (remaining, tick) => remaining.or(tick.map(|tick| tick.0)),
+            };
+
+            match self.recv_job(&signal, wait) {
                 Ok(t) => {
-                    job = Some(t);
+                    job = t;
                 }
                 Err(RecvTimeoutError::Disconnected) => {
-                    self.inner.config.unmount.as_ref().map(|f| f());
-
-                    self.decrement_worker_count();
+                    self.inner.disconnected();
+                    self.exit();
 
                     return None;
                 }
                 Err(RecvTimeoutError::Timeout) => {
-                    timed_out = true;
+                    if let Some((interval, f)) = tick {
+                        if last_tick.elapsed() >= *interval {
+                            f(&self.context());
+                            last_tick = Instant::now();
+                        }
+                    }
+
+                    timed_out = timeout.is_some_and(|timeout| idle_since.elapsed() >= timeout);
                 }
             }
         }
@@ -96,24 +658,114 @@ impl<T: Job> Worker<T> {
     }
 
     pub fn is_disconnected(&self) -> bool {
-        match self.rx.try_recv() {
-            Err(TryRecvError::Disconnected) => true,
-            _ => false,
+        matches!(self.rx.try_recv(), Err(TryRecvError::Disconnected))
+    }
+
+    fn try_recv(&self) -> Result<Task<T>, TryRecvError> {
+        self.lanes
+            .try_recv(&self.rx, self.inner.local_queue(self.index))
+    }
+
+    // Waits for a job, returning `Ok(None)` when `signal` reports a change to
+    // the pool that the worker has to look at.
+    fn recv_job(
+        &self,
+        signal: &Receiver<()>,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Task<T>>, RecvTimeoutError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        if let Some(strategy) = self.inner.config.idle.as_ref() {
+            let mut attempt = 0;
+
+            loop {
+                match self.try_recv() {
+                    Ok(job) => return Ok(Some(job)),
+                    Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                    Err(TryRecvError::Empty) => {}
+                }
+
+                if let Err(TryRecvError::Disconnected) = signal.try_recv() {
+                    return Ok(None);
+                }
+
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+
+                match strategy.idle(attempt) {
+                    Idle::Retry => attempt = attempt.saturating_add(1),
+                    Idle::Park => break,
+                }
+            }
         }
+
+        self.lanes.recv(
+            &self.rx,
+            self.inner.local_queue(self.index),
+            signal,
+            deadline,
+        )
     }
 
-    fn recv_job(&self, timeout: Option<Duration>) -> Result<T, RecvTimeoutError> {
-        match timeout {
-            Some(timeout) => self.rx.recv_timeout(timeout),
-            None => self.rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    fn context(&self) -> WorkerContext<'_> {
+        WorkerContext {
+            index: self.index,
+            pool_name: self.inner.config.name.as_deref(),
         }
     }
 
+    fn exit(&self) {
+        self.inner.unmount(&self.context());
+
+        self.decrement_worker_count();
+    }
+
     fn decrement_worker_count(&self) {
-        let state = self.inner.state.fetch_dec_worker_count();
+        self.inner.decrement_worker_count();
+    }
+}
+
+/// Workers replaced in a row after dying outside a job, each after twice the
+/// pause of the last, before the pool stops replacing them.
+const RESPAWN_BUDGET: usize = 8;
+
+// Replaces a worker whose thread unwinds outside of a job, e.g. from a hook,
+// so a faulty hook cannot silently shrink the pool.
+struct Sentinel<T: Job> {
+    rx: Receiver<Task<T>>,
+    lanes: Arc<Lanes<T>>,
+    inner: Arc<Inner>,
+    dedicated: bool,
+    busy: bool,
+}
+
+impl<T: Job> Drop for Sentinel<T> {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            return;
+        }
+
+        if self.busy {
+            self.inner.active.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        self.inner.decrement_worker_count();
+
+        // A hook that always panics would otherwise respawn workers forever.
+        let crashes = self.inner.crashes.fetch_add(1, Ordering::SeqCst) + 1;
+        if crashes > RESPAWN_BUDGET || self.inner.state.load().lifecycle() != Lifecycle::Running {
+            return;
+        }
+        thread::sleep(Duration::from_millis(1 << crashes));
 
-        if state.worker_count() == 1 && self.is_disconnected() {
-            self.inner.finalize_instance();
+        if self.dedicated {
+            self.inner
+                .add_dedicated_worker(&self.rx, &self.lanes, &self.inner);
+        } else {
+            let _ = self
+                .inner
+                .add_worker(&self.rx, &self.lanes, None, &self.inner);
         }
     }
 }
@@ -0,0 +1,42 @@
+#![cfg(feature = "alloc-stats")]
+
+extern crate multix;
+
+use multix::alloc_stats::CountingAlloc;
+use multix::core::TPBuilder;
+use multix::{Job, ThreadPool};
+use std::alloc::System;
+use std::hint::black_box;
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc::new(System);
+
+struct Fill(usize);
+
+impl Job for Fill {
+    fn call(self) {
+        black_box(vec![0u8; self.0]);
+    }
+
+    fn kind(&self) -> Option<&'static str> {
+        Some("fill")
+    }
+}
+
+#[test]
+fn job_allocations_are_recorded() {
+    let pool: ThreadPool<Fill> = TPBuilder::single().build();
+    let outcomes = pool.outcomes();
+
+    pool.send_reporting(Fill(4096)).unwrap();
+    pool.send_reporting(Fill(0)).unwrap();
+
+    let big = outcomes.recv().unwrap();
+    let empty = outcomes.recv().unwrap();
+    assert!(big.allocated.bytes >= 4096);
+    assert!(big.allocated.count >= 1);
+    assert_eq!(0, empty.allocated.bytes);
+
+    let stats = pool.kind_stats()["fill"];
+    assert!(stats.allocated.bytes >= 4096);
+}
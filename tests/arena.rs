@@ -0,0 +1,32 @@
+#![cfg(feature = "arena")]
+
+extern crate multix;
+
+use multix::core::TPBuilder;
+use std::sync::mpsc;
+
+#[test]
+fn arena_is_reset_between_jobs() {
+    let pool = TPBuilder::single().arena(4096).build();
+    let (tx, rx) = mpsc::channel();
+
+    for n in 0..2 {
+        let tx = tx.clone();
+        pool.send(move || {
+            let arena = multix::arena().unwrap();
+            let before = arena.allocated_bytes();
+            let values = arena.alloc_slice_fill_copy(256, n as u8);
+
+            assert!(multix::arena().is_none());
+            tx.send((before, values[255])).unwrap();
+        })
+        .unwrap();
+    }
+
+    let (first, _) = rx.recv().unwrap();
+    let (second, last) = rx.recv().unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(1, last);
+    assert!(multix::arena().is_none());
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "async-std")]
+
+extern crate async_std;
+extern crate multix;
+
+use async_std::task;
+use multix::async_std::spawn_blocking;
+use multix::core::TPBuilder;
+use multix::{JobBox, ThreadPool};
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+#[test]
+fn await_blocking_job() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(2);
+
+    let sum = task::block_on(async {
+        let a = spawn_blocking(&pool, || 20);
+        let b = spawn_blocking(&pool, || 22);
+
+        a.await + b.await
+    });
+
+    assert_eq!(42, sum);
+}
+
+#[test]
+fn refused_job_runs_on_async_std() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(1).name("refusing").build();
+    pool.close();
+
+    let name = task::block_on(spawn_blocking(&pool, || {
+        thread::current().name().map(String::from)
+    }));
+
+    assert!(!name.unwrap_or_default().starts_with("refusing"));
+}
+
+#[test]
+fn panic_resumes_in_awaiting_task() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+
+    let err = panic::catch_unwind(AssertUnwindSafe(|| {
+        task::block_on(spawn_blocking(&pool, || -> u32 { panic!("boom") }))
+    }))
+    .unwrap_err();
+
+    assert_eq!(Some(&"boom".to_string()), err.downcast_ref::<String>());
+}
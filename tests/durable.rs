@@ -0,0 +1,240 @@
+#![cfg(feature = "persistence")]
+
+extern crate multix;
+
+use multix::core::{RejectionPolicy, SpawnPolicy, TPBuilder};
+use multix::durable::{Durable, DurableQueue, JobQueue};
+use multix::{ConfigError, Job, JobBox, Priority, ThreadPool};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Barrier, Mutex};
+use std::{env, fs, io, process};
+
+#[derive(Serialize, Deserialize)]
+struct Noop(u32);
+
+impl Job for Noop {
+    fn call(self) {}
+}
+
+#[test]
+fn replays_unacknowledged_jobs() {
+    let path = env::temp_dir().join(format!("multix-durable-{}.log", process::id()));
+    let _ = fs::remove_file(&path);
+
+    {
+        let queue = DurableQueue::<Noop>::open(&path).unwrap();
+        let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+        let (tx, rx) = mpsc::sync_channel(0);
+
+        pool.send_fn(move || {
+            rx.recv().unwrap();
+        })
+        .unwrap();
+
+        queue.send(&pool, Noop(1)).unwrap();
+        pool.close_force();
+        tx.send(()).unwrap();
+        pool.await_termination();
+
+        assert_eq!(1, queue.pending());
+    }
+
+    let queue = DurableQueue::<Noop>::open(&path).unwrap();
+    assert_eq!(1, queue.pending());
+
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    assert_eq!(1, queue.resume(&pool).unwrap());
+
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(0, queue.pending());
+    queue.compact().unwrap();
+    assert_eq!(0, fs::metadata(&path).unwrap().len());
+
+    fs::remove_file(&path).unwrap();
+}
+
+// Keeps pending jobs in memory, standing in for e.g. a database table.
+#[derive(Default)]
+struct MemoryQueue {
+    jobs: Mutex<BTreeMap<u64, u32>>,
+}
+
+impl JobQueue<Noop> for MemoryQueue {
+    fn push(&self, job: &Noop) -> io::Result<u64> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let id = jobs.keys().next_back().map_or(0, |id| id + 1);
+
+        jobs.insert(id, job.0);
+        Ok(id)
+    }
+
+    fn ack(&self, id: u64) -> io::Result<()> {
+        self.jobs.lock().unwrap().remove(&id);
+        Ok(())
+    }
+
+    fn pending(&self) -> io::Result<Vec<(u64, Noop)>> {
+        let jobs = self.jobs.lock().unwrap();
+        Ok(jobs.iter().map(|(&id, &n)| (id, Noop(n))).collect())
+    }
+
+    fn len(&self) -> usize {
+        self.jobs.lock().unwrap().len()
+    }
+}
+
+#[test]
+fn custom_backend() {
+    let queue = DurableQueue::with_queue(MemoryQueue::default());
+    queue.queue().push(&Noop(7)).unwrap();
+
+    let pool: ThreadPool<Durable<Noop, MemoryQueue>> = ThreadPool::new(1);
+    assert_eq!(1, queue.resume(&pool).unwrap());
+    queue.send(&pool, Noop(8)).unwrap();
+
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(0, queue.pending());
+}
+
+static RESTORED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Serialize, Deserialize)]
+struct Count(usize);
+
+impl Job for Count {
+    fn call(self) {
+        RESTORED.fetch_add(self.0, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn checkpoint_and_restore() {
+    let gate = Arc::new(Barrier::new(2));
+    let pool: ThreadPool<Count> = {
+        let gate = gate.clone();
+        TPBuilder::fixed(1)
+            .mount(move |_| {
+                gate.wait();
+            })
+            .build()
+    };
+
+    for n in 1..3 {
+        pool.send(Count(n)).unwrap();
+    }
+
+    let mut buf = Vec::new();
+    assert_eq!(2, pool.checkpoint(&mut buf).unwrap());
+    assert_eq!(0, pool.queued());
+    gate.wait();
+
+    let pool: ThreadPool<Count> = TPBuilder::new()
+        .size(2)
+        .restore_from::<Count, _>(&buf[..])
+        .unwrap()
+        .build();
+
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(3, RESTORED.load(Ordering::SeqCst));
+}
+
+#[test]
+fn restore_checks_jobs() {
+    let pool: ThreadPool<Noop> = TPBuilder::new()
+        .size(1)
+        .spawn_policy(SpawnPolicy::Manual)
+        .rejection_policy(RejectionPolicy::Abort)
+        .build();
+    pool.send_with_priority(Noop(1), Priority::High).unwrap();
+    pool.send_with_priority(Noop(2), Priority::Low).unwrap();
+    pool.send(Noop(3)).unwrap();
+
+    let mut buf = Vec::new();
+    assert_eq!(3, pool.checkpoint(&mut buf).unwrap());
+    assert_eq!(0, pool.queued());
+
+    let builder = TPBuilder::new()
+        .size(2)
+        .spawn_policy(SpawnPolicy::Manual)
+        .rejection_policy(RejectionPolicy::Abort)
+        .restore_from::<Noop, _>(&buf[..])
+        .unwrap();
+    assert_eq!(
+        Err(ConfigError::RestoredJobType),
+        builder.try_build::<Count>().map(drop)
+    );
+    assert_eq!(
+        Err(ConfigError::QueueSmallerThanBurst { queue: 2, burst: 3 }),
+        builder.try_build::<Noop>().map(drop)
+    );
+    assert_eq!(
+        Err(ConfigError::ConflictingPolicies(
+            "no_workers",
+            "restore_from"
+        )),
+        builder.clone().no_workers().validate()
+    );
+
+    // Nothing runs them, yet building does not wait for room.
+    let pool: ThreadPool<Noop> = builder.queue_capacity(3).try_build().unwrap();
+    assert_eq!(3, pool.queued());
+}
+
+#[cfg(feature = "fault-injection")]
+#[test]
+fn refused_restore_keeps_jobs() {
+    use multix::fault::FaultPolicy;
+
+    let mut buf = Vec::new();
+    multix::durable::write_jobs(&mut buf, &[Noop(1), Noop(2), Noop(3)]).unwrap();
+
+    let builder = TPBuilder::new()
+        .size(1)
+        .spawn_policy(SpawnPolicy::Manual)
+        .unbounded()
+        .restore_from::<Noop, _>(&buf[..])
+        .unwrap();
+    assert_eq!(
+        Err(ConfigError::RestoreRefused),
+        builder
+            .clone()
+            .fault_injection(FaultPolicy::new(1).fail_sends(1.0))
+            .try_build::<Noop>()
+            .map(drop)
+    );
+
+    let pool: ThreadPool<Noop> = builder.try_build().unwrap();
+    assert_eq!(3, pool.queued());
+}
+
+#[test]
+fn stats_serialize() {
+    let pool: ThreadPool<Count> = ThreadPool::new(3);
+    let json = serde_json::to_string(&pool.stats()).unwrap();
+
+    assert_eq!(
+        concat!(
+            r#"{"lifecycle":"Running","size":3,"workers":0,"queued":0,"active":0,"#,
+            r#""idle":0,"submitted":0,"completed":0,"panicked":0}"#
+        ),
+        json
+    );
+}
+
+#[test]
+fn dump_serialize() {
+    let pool: ThreadPool<Count> = ThreadPool::new(1);
+    let json = serde_json::to_value(pool.dump()).unwrap();
+
+    assert_eq!(json["stats"]["size"], 1);
+    assert!(json["workers"].as_array().unwrap().is_empty());
+    assert!(json["kinds"].as_object().unwrap().is_empty());
+}
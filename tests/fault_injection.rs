@@ -0,0 +1,59 @@
+#![cfg(feature = "fault-injection")]
+
+extern crate multix;
+
+use multix::core::TPBuilder;
+use multix::fault::FaultPolicy;
+use multix::{JobBox, ThreadPool};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn injected_faults() {
+    let panicked = Arc::new(AtomicUsize::new(0));
+    let ran = Arc::new(AtomicUsize::new(0));
+    let counter = panicked.clone();
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(2)
+        .fault_injection(FaultPolicy::new(7).fail_sends(0.5).panic_jobs(0.5))
+        .panic_handler(move |payload| {
+            assert_eq!(Some(&"injected fault"), payload.downcast_ref::<&str>());
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .build();
+
+    let refused = (0..200)
+        .filter(|_| {
+            let ran = ran.clone();
+
+            pool.send_fn(move || {
+                ran.fetch_add(1, Ordering::SeqCst);
+            })
+            .is_err()
+        })
+        .count();
+    pool.close();
+    pool.await_termination();
+
+    let (panicked, ran) = (panicked.load(Ordering::SeqCst), ran.load(Ordering::SeqCst));
+    assert!(refused > 50 && refused < 150, "{} refused", refused);
+    assert!(
+        panicked > 10 && ran > 10,
+        "{} panicked, {} ran",
+        panicked,
+        ran
+    );
+    assert_eq!(200, refused + panicked + ran);
+}
+
+#[test]
+fn delayed_dequeues() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single()
+        .fault_injection(FaultPolicy::new(1).delay_dequeues(1.0, Duration::from_millis(20)))
+        .build();
+
+    let started = Instant::now();
+    assert_eq!(Ok(1), pool.submit(|| 1).join());
+    assert!(started.elapsed() >= Duration::from_millis(20));
+}
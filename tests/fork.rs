@@ -0,0 +1,153 @@
+#![cfg(unix)]
+
+extern crate multix;
+
+use multix::core::TPBuilder;
+use multix::{JobBox, ThreadPool};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Barrier};
+use std::thread;
+use std::time::Duration;
+
+fn run_job(pool: &ThreadPool<Box<dyn JobBox>>) -> bool {
+    let (tx, rx) = mpsc::channel();
+
+    pool.send_fn(move || tx.send(()).unwrap()).unwrap();
+    rx.recv_timeout(Duration::from_secs(5)).is_ok()
+}
+
+#[test]
+fn pool_survives_fork() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(2).build();
+    assert!(run_job(&pool));
+
+    let guard = pool.prepare_fork();
+    let pid = unsafe { libc::fork() };
+    assert!(pid >= 0);
+
+    if pid == 0 {
+        let ok = panic::catch_unwind(AssertUnwindSafe(|| {
+            pool.reinit_after_fork(guard);
+            pool.stats().workers == 2 && run_job(&pool)
+        }));
+
+        unsafe { libc::_exit(if ok.unwrap_or(false) { 0 } else { 1 }) };
+    }
+
+    drop(guard);
+    assert!(run_job(&pool));
+
+    let mut status = 0;
+    assert_eq!(pid, unsafe { libc::waitpid(pid, &mut status, 0) });
+    assert!(libc::WIFEXITED(status));
+    assert_eq!(0, libc::WEXITSTATUS(status));
+
+    pool.close();
+    pool.await_termination();
+}
+
+#[test]
+fn prepare_fork_while_scope_helps() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(2).build();
+    let (started_tx, started_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel();
+    {
+        let pool = pool.clone();
+        pool.clone()
+            .send_fn(move || {
+                started_tx.send(()).unwrap();
+                thread::sleep(Duration::from_millis(50));
+
+                // Both jobs must run at once, one of them on the other worker.
+                let barrier = Barrier::new(2);
+                pool.scope(|s| {
+                    s.send(|| {
+                        barrier.wait();
+                    });
+                    s.send(|| {
+                        barrier.wait();
+                    });
+                });
+                done_tx.send(()).unwrap();
+            })
+            .unwrap();
+    }
+
+    started_rx.recv().unwrap();
+    let (fork_tx, fork_rx) = mpsc::channel();
+    {
+        let pool = pool.clone();
+        thread::spawn(move || {
+            let guard = pool.prepare_fork();
+            fork_tx.send(()).unwrap();
+            drop(guard);
+        });
+    }
+
+    assert!(fork_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    assert!(done_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    assert!(run_job(&pool));
+
+    pool.close();
+    pool.await_termination();
+}
+
+#[test]
+fn child_queues_lifo_slots() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(2).build();
+    let (started_tx, started_rx) = mpsc::channel();
+    let (slot_tx, slot_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel();
+    {
+        let pool = pool.clone();
+        pool.clone()
+            .send_fn(move || {
+                started_tx.send(()).unwrap();
+                thread::sleep(Duration::from_millis(50));
+
+                // Left in the slot while the scope waits out the fork.
+                pool.send_local(Box::new(move || slot_tx.send(()).unwrap()))
+                    .unwrap();
+                pool.scope(|s| s.send(|| {}));
+                done_tx.send(()).unwrap();
+            })
+            .unwrap();
+    }
+
+    started_rx.recv().unwrap();
+    let guard = pool.prepare_fork();
+    let pid = unsafe { libc::fork() };
+    assert!(pid >= 0);
+
+    if pid == 0 {
+        let ok = panic::catch_unwind(AssertUnwindSafe(|| {
+            pool.reinit_after_fork(guard);
+            let slot_ran = slot_rx.recv_timeout(Duration::from_secs(5)).is_ok();
+
+            let (tx, rx) = mpsc::channel();
+            pool.send_after(
+                Box::new(move || tx.send(()).unwrap()),
+                Duration::from_millis(10),
+            )
+            .unwrap();
+            let timer_ran = rx.recv_timeout(Duration::from_secs(5)).is_ok();
+
+            thread::sleep(Duration::from_millis(50));
+            slot_ran && timer_ran && pool.dump().workers.len() == 2
+        }));
+
+        unsafe { libc::_exit(if ok.unwrap_or(false) { 0 } else { 1 }) };
+    }
+
+    drop(guard);
+    assert!(slot_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    assert!(done_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+    let mut status = 0;
+    assert_eq!(pid, unsafe { libc::waitpid(pid, &mut status, 0) });
+    assert!(libc::WIFEXITED(status));
+    assert_eq!(0, libc::WEXITSTATUS(status));
+
+    pool.close();
+    pool.await_termination();
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "futures")]
+
+extern crate futures;
+extern crate multix;
+
+use futures::executor::block_on;
+use futures::task::SpawnExt;
+use multix::core::{RejectionPolicy, TPBuilder};
+use multix::{JobBox, JobError, ThreadPool};
+use std::future;
+use std::task::Poll;
+use std::time::Duration;
+
+#[test]
+fn spawn_future_on_pool() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(2);
+
+    let inner = pool.submit(|| 20);
+    let sleep = pool.sleep(Duration::from_millis(10));
+    let handle = pool.spawn_future(async move {
+        sleep.await;
+        inner.await.unwrap() + 22
+    });
+
+    assert_eq!(Ok(42), handle.join());
+}
+
+#[test]
+fn panicking_future() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    let handle = pool.spawn_future(async { panic!("boom") });
+    let result: Result<(), _> = handle.join();
+
+    assert_eq!(Err(JobError::Panicked("boom".to_string())), result);
+}
+
+#[test]
+fn pool_as_spawner() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(2);
+
+    let handle = pool.spawn_with_handle(async { 6 * 7 }).unwrap();
+    assert_eq!(42, block_on(handle));
+
+    pool.close();
+    assert!(pool.spawn(async {}).unwrap_err().is_shutdown());
+}
+
+#[test]
+fn wake_from_worker_of_full_pool() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(1)
+        .queue_capacity(1)
+        .rejection_policy(RejectionPolicy::Block)
+        .build();
+    let mut filled = false;
+
+    let handle = pool.clone().spawn_future(future::poll_fn(move |cx| {
+        if filled {
+            return Poll::Ready(7);
+        }
+
+        // The only worker is polling this, so nothing makes room.
+        pool.send_fn(|| {}).unwrap();
+        filled = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }));
+
+    assert_eq!(Some(Ok(7)), handle.join_timeout(Duration::from_secs(5)));
+}
@@ -0,0 +1,39 @@
+#![cfg(all(unix, feature = "ipc"))]
+
+extern crate multix;
+
+use multix::ipc::{IpcClient, IpcServer};
+use multix::{Job, ThreadPool};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, process};
+
+static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Serialize, Deserialize)]
+struct Add(usize);
+
+impl Job for Add {
+    fn call(self) {
+        RECEIVED.fetch_add(self.0, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn submit_over_socket() {
+    let path = env::temp_dir().join(format!("multix-ipc-{}.sock", process::id()));
+    let pool: ThreadPool<Add> = ThreadPool::new(2);
+    let mut server = IpcServer::bind(&path, pool.clone()).unwrap();
+
+    let mut client = IpcClient::connect(server.path()).unwrap();
+    client.send(&Add(2)).unwrap();
+    client.send(&Add(3)).unwrap();
+
+    server.close();
+    assert!(!path.exists());
+
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(5, RECEIVED.load(Ordering::SeqCst));
+}
@@ -1,13 +1,31 @@
 extern crate multix;
 
-use multix::ThreadPool;
+use multix::affinity::{self, CoreKind};
+use multix::alloc::Budget;
+use multix::core::{
+    Config, QueueCapacity, RejectionPolicy, Scheduler, SpawnPolicy, StackSize, TPBuilder,
+};
+use multix::error::STACK_GRANULARITY;
+use multix::handle;
+use multix::lifecycle::Lifecycle;
+use multix::test::StepPool;
+use multix::Router;
+use multix::{
+    ConfigError, Executor, GroupTooLarge, Job, JobBox, JobContext, JobError, JobGuard, JobId,
+    JobMeta, JobStatus, Next, OnDrop, PanicPolicy, Priority, SubmitError, ThreadPool,
+};
+use std::future::Future;
+use std::hint::black_box;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::pin;
 use std::sync::mpsc;
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Barrier, Mutex,
 };
+use std::task::{Context, Poll, Wake, Waker};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[test]
 fn one_thread() {
@@ -81,6 +99,48 @@ fn two_thread_job_on() {
     }
 }
 
+#[test]
+fn close_rejects_new_jobs() {
+    let pool = ThreadPool::new(1);
+    pool.close();
+
+    assert!(pool.send(|| {}).is_err());
+
+    pool.await_termination();
+    assert!(pool.is_terminated());
+}
+
+#[test]
+fn close_force_drops_queued_jobs() {
+    let pool = ThreadPool::new(1);
+    let ran = Arc::new(AtomicUsize::new(0));
+    let job = |gate: Option<(mpsc::Sender<()>, mpsc::Receiver<()>)>| {
+        let ran = ran.clone();
+        move || {
+            if let Some((started, gate)) = gate {
+                started.send(()).unwrap();
+                let _ = gate.recv();
+            }
+            ran.fetch_add(1, Ordering::SeqCst);
+        }
+    };
+    let (started_tx, started_rx) = mpsc::channel();
+    let (gate_tx, gate_rx) = mpsc::channel();
+
+    pool.send(job(Some((started_tx, gate_rx)))).unwrap();
+    started_rx.recv().unwrap();
+    pool.send(job(None)).unwrap();
+
+    pool.close_force();
+    assert!(pool.is_terminating() || pool.is_terminated());
+    assert!(pool.send(job(None)).is_err());
+
+    drop(gate_tx);
+    pool.await_termination();
+    assert_eq!(1, ran.load(Ordering::SeqCst));
+    assert!(pool.is_terminated());
+}
+
 #[test]
 fn threads_shutdown_drop() {
     let pool = ThreadPool::single_thread();
@@ -104,6 +164,2834 @@ fn threads_shutdown_drop() {
     assert!(pool.is_terminated());
 }
 
+#[test]
+fn drop_last_handle_closes_pool() {
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(1)
+        .unmount(move |_| tx.lock().unwrap().send(()).unwrap())
+        .build();
+    let atom = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..10 {
+        let atom = atom.clone();
+        pool.send_fn(move || {
+            atom.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    }
+
+    let handle = pool.clone();
+    drop(pool);
+    assert!(!handle.is_terminating());
+    drop(handle);
+
+    rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(10, atom.load(Ordering::SeqCst));
+}
+
+#[test]
+fn idle_workers_retire() {
+    let unmounted = Arc::new(AtomicUsize::new(0));
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(2)
+        .timeout(Duration::from_millis(20))
+        .unmount({
+            let unmounted = unmounted.clone();
+            move |_| {
+                unmounted.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .build();
+
+    pool.prestart_core_threads();
+    assert_eq!(2, pool.stats().workers);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pool.stats().workers > 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(0, pool.stats().workers);
+    assert_eq!(2, unmounted.load(Ordering::SeqCst));
+
+    let (tx, rx) = mpsc::channel();
+    pool.send_fn(move || tx.send(()).unwrap()).unwrap();
+    rx.recv_timeout(Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn core_and_max_size() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .core_size(1)
+        .max_size(3)
+        .queue_capacity(1)
+        .timeout(Duration::from_millis(20))
+        .build();
+    let barrier = Arc::new(Barrier::new(4));
+    let blocking = |pool: &ThreadPool<Box<dyn JobBox>>, active| {
+        let barrier = barrier.clone();
+        pool.send_fn(move || {
+            barrier.wait();
+        })
+        .unwrap();
+        while pool.stats().active < active {
+            thread::yield_now();
+        }
+    };
+
+    blocking(&pool, 1);
+    pool.send_fn(|| {}).unwrap();
+    assert_eq!(1, pool.stats().workers);
+
+    blocking(&pool, 2);
+    blocking(&pool, 3);
+    assert_eq!(3, pool.stats().workers);
+    assert!(pool.try_send_fn(|| {}).unwrap_err().is_full());
+    assert!(pool
+        .send_fn_timeout(|| {}, Duration::from_millis(10))
+        .unwrap_err()
+        .is_timeout());
+
+    barrier.wait();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pool.stats().workers > 1 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(5));
+    }
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(1, pool.stats().workers);
+
+    let err = TPBuilder::new()
+        .core_size(4)
+        .max_size(2)
+        .try_build::<fn()>()
+        .unwrap_err();
+    assert_eq!(ConfigError::MaxBelowCore { core: 4, max: 2 }, err);
+}
+
+#[test]
+fn resize_pool() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(1).build();
+    let barrier = Arc::new(Barrier::new(5));
+
+    for _ in 0..4 {
+        let barrier = barrier.clone();
+        pool.send_fn(move || {
+            barrier.wait();
+        })
+        .unwrap();
+    }
+    assert_eq!(1, pool.stats().workers);
+
+    pool.resize(4).unwrap();
+    assert_eq!(4, pool.stats().size);
+    barrier.wait();
+    assert_eq!(4, pool.stats().workers);
+
+    pool.resize(2).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pool.stats().workers > 2 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(2, pool.stats().workers);
+
+    assert_eq!(Err(ConfigError::ZeroSize), pool.resize(0));
+}
+
+#[test]
+fn resize_pool_with_max_size() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(4)
+        .max_size(4)
+        .spawn_policy(SpawnPolicy::Eager)
+        .build();
+    assert_eq!(4, pool.stats().workers);
+
+    pool.resize(1).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pool.stats().workers > 1 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(1, pool.stats().workers);
+}
+
+#[test]
+fn named_worker_threads() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().name_prefix("my-pool").build();
+    let (tx, rx) = mpsc::channel();
+
+    pool.send_fn(move || tx.send(thread::current().name().map(String::from)).unwrap())
+        .unwrap();
+
+    assert_eq!(Some("my-pool-0".to_string()), rx.recv().unwrap());
+}
+
+#[test]
+fn idle_workers_wake_on_control() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(4).build();
+    pool.prestart_core_threads();
+
+    pool.resize(1).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while pool.stats().workers > 1 && Instant::now() < deadline {
+        thread::yield_now();
+    }
+    assert_eq!(1, pool.stats().workers);
+
+    let started = Instant::now();
+    pool.close();
+    pool.await_termination();
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn idle_strategies() {
+    use multix::idle::{BusySpin, Idle, IdleStrategy, SleepBackoff, SpinThenPark};
+
+    struct Counting(Arc<AtomicUsize>, SpinThenPark);
+
+    impl IdleStrategy for Counting {
+        fn idle(&self, attempt: u32) -> Idle {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            self.1.idle(attempt)
+        }
+    }
+
+    fn run(builder: TPBuilder) {
+        let pool: ThreadPool<Box<dyn JobBox>> = builder.build();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..20 {
+            let count = count.clone();
+            pool.send_fn(move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        pool.close();
+        pool.await_termination();
+        assert_eq!(20, count.load(Ordering::SeqCst));
+    }
+
+    let polls = Arc::new(AtomicUsize::new(0));
+    run(TPBuilder::fixed(2).idle_strategy(Counting(polls.clone(), SpinThenPark { spins: 100 })));
+    assert!(polls.load(Ordering::SeqCst) > 0);
+
+    run(TPBuilder::fixed(2).idle_strategy(BusySpin));
+    run(TPBuilder::fixed(2).idle_strategy(SleepBackoff {
+        min: Duration::from_micros(10),
+        max: Duration::from_millis(1),
+    }));
+}
+
+#[test]
+fn shutdown_with_deadline() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    for _ in 0..3 {
+        pool.send_fn(|| thread::sleep(Duration::from_millis(5)))
+            .unwrap();
+    }
+    assert_eq!(
+        multix::ShutdownResult::Drained,
+        pool.shutdown(Duration::from_secs(5))
+    );
+
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::channel::<()>();
+    pool.send_fn(move || {
+        let _ = rx.recv();
+    })
+    .unwrap();
+    for _ in 0..3 {
+        pool.send_fn(|| {}).unwrap();
+    }
+    while pool.stats().active < 1 {
+        thread::yield_now();
+    }
+
+    assert_eq!(
+        multix::ShutdownResult::Forced {
+            abandoned: 3,
+            critical: 0,
+            running: 1
+        },
+        pool.shutdown(Duration::from_millis(20))
+    );
+    drop(tx);
+    assert!(pool.await_termination_timeout(Duration::from_secs(5)));
+}
+
+#[test]
+fn work_stealing_scheduler() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(4)
+        .scheduler(Scheduler::WorkStealing)
+        .build();
+    let (tx, rx) = mpsc::channel();
+
+    {
+        let pool = pool.clone();
+        pool.clone()
+            .send_fn(move || {
+                for _ in 0..40 {
+                    let tx = tx.clone();
+                    pool.send_fn(move || {
+                        thread::sleep(Duration::from_millis(2));
+                        tx.send(thread::current().id()).unwrap();
+                    })
+                    .unwrap();
+                }
+            })
+            .unwrap();
+    }
+
+    let threads: Vec<_> = rx.iter().take(40).collect();
+    assert!(threads.iter().any(|&id| id != threads[0]));
+
+    pool.close();
+    pool.await_termination();
+}
+
+#[test]
+fn lifo_slot() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::channel();
+    let job = |label| -> Box<dyn JobBox> {
+        let tx = tx.clone();
+        Box::new(move || tx.send(label).unwrap())
+    };
+
+    pool.send_local(job("outside")).unwrap();
+    assert_eq!("outside", rx.recv().unwrap());
+
+    let (queued, first, second) = (job("queued"), job("first"), job("second"));
+    {
+        let pool = pool.clone();
+        pool.clone()
+            .send_fn(move || {
+                pool.send(queued).unwrap();
+                pool.send_local(first).unwrap();
+                pool.send_local(second).unwrap();
+            })
+            .unwrap();
+    }
+
+    let order: Vec<_> = rx.iter().take(3).collect();
+    assert_eq!(vec!["second", "queued", "first"], order);
+
+    pool.close();
+    pool.await_termination();
+    let stats = pool.stats();
+    assert_eq!((5, 5), (stats.submitted, stats.completed));
+}
+
+#[test]
+fn lifo_slot_full_queue() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(1)
+        .queue_capacity(1)
+        .rejection_policy(RejectionPolicy::Abort)
+        .build();
+    let (tx, rx) = mpsc::channel();
+    let job = |label| -> Box<dyn JobBox> {
+        let tx = tx.clone();
+        Box::new(move || tx.send(label).unwrap())
+    };
+
+    let (queued, first, second) = (job("queued"), job("first"), job("second"));
+    {
+        let pool = pool.clone();
+        pool.clone()
+            .send_fn(move || {
+                pool.send(queued).unwrap();
+                pool.send_local(first).unwrap();
+                pool.send_local(second).unwrap();
+                assert_eq!(4, pool.stats().submitted);
+            })
+            .unwrap();
+    }
+
+    let order: Vec<_> = rx.iter().take(3).collect();
+    assert_eq!(vec!["second", "first", "queued"], order);
+
+    pool.close();
+    pool.await_termination();
+    let stats = pool.stats();
+    assert_eq!((4, 4), (stats.submitted, stats.completed));
+}
+
+#[test]
+fn lifo_slot_is_queued() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let (sent_tx, sent_rx) = mpsc::channel();
+    let (gate_tx, gate_rx) = mpsc::channel::<()>();
+    let ran = Arc::new(AtomicBool::new(false));
+    {
+        let (pool, ran) = (pool.clone(), ran.clone());
+        pool.clone()
+            .send_fn(move || {
+                pool.send_fn(|| {}).unwrap();
+                pool.send_local(Box::new(move || ran.store(true, Ordering::SeqCst)))
+                    .unwrap();
+                sent_tx.send(()).unwrap();
+                let _ = gate_rx.recv();
+            })
+            .unwrap();
+    }
+
+    sent_rx.recv().unwrap();
+    assert_eq!(2, pool.queued());
+
+    assert_eq!(2, pool.close_force().len());
+    assert_eq!(0, pool.queued());
+    drop(gate_tx);
+    pool.await_termination();
+    assert!(!ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn critical_jobs() {
+    let blocked = |policy| {
+        let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+            .size(1)
+            .queue_capacity(2)
+            .rejection_policy(policy)
+            .build();
+        let (tx, rx) = mpsc::channel::<()>();
+
+        pool.send_fn(move || {
+            let _ = rx.recv();
+        })
+        .unwrap();
+        while pool.stats().active < 1 {
+            thread::yield_now();
+        }
+
+        (pool, tx)
+    };
+    let ran = Arc::new(AtomicUsize::new(0));
+    let job = || -> Box<dyn JobBox> {
+        let ran = ran.clone();
+        Box::new(move || {
+            ran.fetch_add(1, Ordering::SeqCst);
+        })
+    };
+
+    let (pool, _release) = blocked(RejectionPolicy::Discard);
+    pool.send_fn(|| {}).unwrap();
+    pool.send_fn(|| {}).unwrap();
+    pool.send_critical(job()).unwrap();
+    assert_eq!(1, ran.load(Ordering::SeqCst));
+
+    let (pool, _release) = blocked(RejectionPolicy::DiscardOldest);
+    pool.send_critical(job()).unwrap();
+    pool.send_fn(|| {}).unwrap();
+    pool.send_fn(|| {}).unwrap();
+    assert_eq!(2, ran.load(Ordering::SeqCst));
+
+    let (pool, release) = blocked(RejectionPolicy::Block);
+    pool.send_critical(job()).unwrap();
+    pool.send(job()).unwrap();
+    assert_eq!(
+        multix::ShutdownResult::Forced {
+            abandoned: 1,
+            critical: 1,
+            running: 1
+        },
+        pool.shutdown(Duration::from_millis(20))
+    );
+    assert_eq!(3, ran.load(Ordering::SeqCst));
+    drop(release);
+    assert!(pool.await_termination_timeout(Duration::from_secs(5)));
+}
+
+#[test]
+fn preset_builders() {
+    let fixed: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(1).build();
+    let barrier = Arc::new(Barrier::new(2));
+    let (tx, rx) = mpsc::channel();
+
+    {
+        let barrier = barrier.clone();
+        fixed
+            .send_fn(move || {
+                tx.send(()).unwrap();
+                barrier.wait();
+            })
+            .unwrap();
+    }
+
+    rx.recv().unwrap();
+    fixed.send_fn(|| {}).unwrap();
+    fixed.send_fn(|| {}).unwrap();
+
+    assert_eq!(1, fixed.size());
+    assert_eq!(2, fixed.queued());
+    barrier.wait();
+
+    let cached: ThreadPool<Box<dyn JobBox>> = TPBuilder::cached().build();
+    let barrier = Arc::new(Barrier::new(4));
+
+    for _ in 0..3 {
+        let barrier = barrier.clone();
+        cached
+            .send_fn(move || {
+                barrier.wait();
+            })
+            .unwrap();
+    }
+
+    assert_eq!(3, cached.size());
+    barrier.wait();
+}
+
+#[test]
+fn from_config() {
+    let config = Config {
+        size: 3,
+        queue_capacity: Some(QueueCapacity::Unbounded),
+        ..Config::default()
+    };
+    let pool: ThreadPool<fn()> = ThreadPool::from_config(config);
+
+    assert_eq!(3, pool.stats().size);
+}
+
+#[test]
+fn reusable_builder() {
+    let builder = TPBuilder::new().size(2).name("shard");
+    let first: ThreadPool<fn()> = builder.build();
+    let second: ThreadPool<fn()> = builder.clone().size(4).build();
+    let third: ThreadPool<fn()> = builder.build();
+
+    assert_eq!(2, first.stats().size);
+    assert_eq!(4, second.stats().size);
+    assert_eq!(2, third.stats().size);
+}
+
+#[test]
+fn invalid_config() {
+    let zero = TPBuilder::new().size(0);
+    let tiny_stack = TPBuilder::new().stack_size(1024);
+
+    assert_eq!(Err(ConfigError::ZeroSize), zero.validate());
+    assert!(zero.try_build::<fn()>().is_err());
+    assert!(matches!(
+        tiny_stack.validate(),
+        Err(ConfigError::StackTooSmall {
+            requested: 1024,
+            ..
+        })
+    ));
+    assert_eq!(
+        Err(ConfigError::StackTooLarge {
+            requested: usize::MAX
+        }),
+        TPBuilder::new().stack_size(usize::MAX).validate()
+    );
+    assert_eq!(
+        Err(ConfigError::ConflictingPolicies(
+            "SpawnPolicy::Manual",
+            "RejectionPolicy::Block with a bounded queue"
+        )),
+        TPBuilder::new()
+            .spawn_policy(SpawnPolicy::Manual)
+            .queue_capacity(4)
+            .validate()
+    );
+    assert_eq!(
+        Ok(()),
+        TPBuilder::new()
+            .spawn_policy(SpawnPolicy::Manual)
+            .unbounded()
+            .validate()
+    );
+    assert_eq!(
+        Err(ConfigError::ConflictingPolicies(
+            "queue_capacity(0)",
+            "RejectionPolicy::DiscardOldest"
+        )),
+        TPBuilder::cached()
+            .rejection_policy(RejectionPolicy::DiscardOldest)
+            .validate()
+    );
+}
+
+#[test]
+fn stack_sizes() {
+    let odd = Config {
+        stack_size: Some(100_000),
+        ..Config::default()
+    };
+    assert_eq!(0, odd.thread_stack_size().unwrap() % STACK_GRANULARITY);
+    assert!(odd.thread_stack_size().unwrap() >= 100_000);
+    assert_eq!(Some(64 * 1024), StackSize::Small.bytes());
+    assert_eq!(None, StackSize::Default.bytes());
+
+    fn recurse(depth: u64) -> u64 {
+        let frame = black_box([depth as u8; 1024]);
+
+        match depth {
+            0 => 0,
+            _ => recurse(depth - 1) + frame[0] as u64 % 2,
+        }
+    }
+
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(1)
+        .stack_preset(StackSize::DeepRecursion)
+        .build();
+
+    // Well past the default 2 MiB stack.
+    let deep = pool.submit(|| recurse(16 * 1024));
+    assert_eq!(Ok(8 * 1024), deep.join());
+}
+
+#[test]
+fn runtime_hooks() {
+    let pool = ThreadPool::new(1);
+    let (tx, rx) = mpsc::channel();
+
+    let tx_mount = tx.clone();
+    let _mount = pool.add_mount_hook(move |_| tx_mount.send("mounted").unwrap());
+    let tx_unmount = tx.clone();
+    let _unmount = pool.add_unmount_hook(move |_| tx_unmount.send("unmounted").unwrap());
+    let tx_removed = tx.clone();
+    pool.add_mount_hook(move |_| tx_removed.send("removed").unwrap())
+        .remove();
+
+    pool.send(move || tx.send("hey").unwrap()).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    let msgs: Vec<&str> = rx.try_iter().collect();
+    assert_eq!(vec!["mounted", "hey", "unmounted"], msgs);
+}
+
+#[test]
+fn core_preferences() {
+    assert_eq!(None, affinity::cores_of(CoreKind::Any));
+    if cfg!(target_os = "linux") {
+        assert!(thread::spawn(|| affinity::pin_current(&[0]))
+            .join()
+            .unwrap());
+    }
+
+    for kind in [CoreKind::Performance, CoreKind::Efficiency] {
+        let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(2).prefer_cores(kind).build();
+
+        assert_eq!(Ok(4), pool.submit(|| 2 + 2).join());
+    }
+}
+
+#[test]
+fn pinned_workers() {
+    let allowed = match affinity::current() {
+        Some(allowed) => allowed,
+        None => return,
+    };
+    let pool: ThreadPool<Box<dyn JobBox>> =
+        TPBuilder::new().size(2).affinity(vec![allowed[0]]).build();
+    assert_eq!(
+        Ok(Some(vec![allowed[0]])),
+        pool.submit(affinity::current).join()
+    );
+
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(2).pin_workers(true).build();
+    let pinned = pool.submit(affinity::current).join().unwrap().unwrap();
+    assert_eq!(1, pinned.len());
+
+    assert_eq!(
+        Err(ConfigError::NoCpus),
+        TPBuilder::new().affinity(Vec::new()).validate()
+    );
+}
+
+#[test]
+fn hooks_know_their_worker() {
+    let mounted = Arc::new(Mutex::new(Vec::new()));
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(3)
+        .name("pinned")
+        .mount({
+            let mounted = mounted.clone();
+            move |worker| {
+                assert_eq!(Some("pinned"), worker.pool_name());
+                mounted.lock().unwrap().push(worker.index());
+            }
+        })
+        .build();
+
+    pool.prestart_core_threads();
+    pool.close();
+    pool.await_termination();
+
+    let mut mounted = mounted.lock().unwrap().clone();
+    mounted.sort();
+    assert_eq!(vec![0, 1, 2], mounted);
+}
+
+#[test]
+fn job_context() {
+    let pool = ThreadPool::new(1);
+    let (tx, rx) = mpsc::channel();
+
+    pool.send_with_context(
+        move || {
+            let context = JobContext::current().unwrap();
+            tx.send(*context.get::<u64>().unwrap()).unwrap();
+        },
+        JobContext::new(42u64),
+    )
+    .unwrap();
+
+    assert_eq!(42, rx.recv().unwrap());
+    assert!(JobContext::current().is_none());
+}
+
+#[test]
+fn outcome_stream() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    let outcomes = pool.outcomes();
+
+    let ok = pool.send_reporting_named(Box::new(|| {}), "ok").unwrap();
+    let failed = pool.send_reporting(Box::new(|| panic!("boom"))).unwrap();
+    pool.send_fn(|| {}).unwrap();
+
+    let first = outcomes.recv().unwrap();
+    assert_eq!(ok, first.id);
+    assert_eq!(Some("ok".to_string()), first.name);
+    assert_eq!(Ok(()), first.result);
+
+    let second = outcomes.recv().unwrap();
+    assert_eq!(failed, second.id);
+    assert_eq!(Err(JobError::Panicked("boom".to_string())), second.result);
+
+    pool.close();
+    pool.await_termination();
+    assert!(outcomes.try_recv().is_err());
+}
+
+#[test]
+fn display_summary() {
+    let pool: ThreadPool<fn()> = TPBuilder::new().name("io").size(8).build();
+
+    assert_eq!(
+        "pool \"io\" [running] workers=0/8 queued=0 inflight=0",
+        pool.to_string()
+    );
+}
+
+#[test]
+fn handle_drop_policy() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::sync_channel(0);
+    let ran = Arc::new(AtomicUsize::new(0));
+
+    pool.send_fn(move || {
+        rx.recv().unwrap();
+    })
+    .unwrap();
+
+    let counter = ran.clone();
+    let (job, handle) = handle::wrap(move || counter.fetch_add(1, Ordering::SeqCst));
+    pool.send_fn(job).unwrap();
+    drop(handle.on_drop(OnDrop::Cancel));
+
+    let counter = ran.clone();
+    let (job, handle) = handle::wrap(move || counter.fetch_add(10, Ordering::SeqCst));
+    pool.send_fn(job).unwrap();
+    handle.detach();
+
+    tx.send(()).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(10, ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn pool_waker() {
+    let pool = ThreadPool::new(2);
+    let (tx, rx) = mpsc::channel();
+
+    let waker = multix::waker_for(&pool, move || {
+        let tx = tx.clone();
+        move || tx.send(thread::current().id()).unwrap()
+    });
+
+    waker.wake_by_ref();
+    waker.wake();
+
+    for _ in 0..2 {
+        assert_ne!(thread::current().id(), rx.recv().unwrap());
+    }
+}
+
+fn block_on<F: Future>(pool: &ThreadPool<Box<dyn JobBox>>, future: F) -> F::Output {
+    let current = thread::current();
+    let waker = multix::waker_for(pool, move || {
+        let current = current.clone();
+        Box::new(move || current.unpark()) as Box<dyn JobBox>
+    });
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn timer_futures() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+
+    let start = Instant::now();
+    block_on(&pool, pool.sleep(Duration::from_millis(50)));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    let slow = pool.sleep(Duration::from_secs(10));
+    let result = block_on(&pool, pool.timeout(Duration::from_millis(20), slow));
+    assert_eq!(Err(multix::Elapsed), result);
+
+    let fast = async { 7 };
+    let result = block_on(&pool, pool.timeout(Duration::from_secs(10), fast));
+    assert_eq!(Ok(7), result);
+}
+
+#[test]
+fn sleep_registers_once() {
+    struct Count(AtomicUsize);
+
+    impl Wake for Count {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    let (first, second) = (
+        Arc::new(Count(AtomicUsize::new(0))),
+        Arc::new(Count(AtomicUsize::new(0))),
+    );
+    let mut sleep = pin!(pool.sleep(Duration::from_millis(20)));
+
+    let waker = Waker::from(first.clone());
+    for _ in 0..100 {
+        assert!(sleep
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+    }
+    let waker = Waker::from(second.clone());
+    assert!(sleep
+        .as_mut()
+        .poll(&mut Context::from_waker(&waker))
+        .is_pending());
+
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(0, first.0.load(Ordering::SeqCst));
+    assert_eq!(1, second.0.load(Ordering::SeqCst));
+    assert!(sleep
+        .as_mut()
+        .poll(&mut Context::from_waker(&waker))
+        .is_ready());
+}
+
+#[test]
+fn shutdown_receiver() {
+    let pool: ThreadPool<fn()> = ThreadPool::new(1);
+    let shutdown = pool.shutdown_receiver();
+
+    assert_eq!(
+        Err(crossbeam_channel::RecvTimeoutError::Timeout),
+        shutdown.recv_timeout(Duration::from_millis(10))
+    );
+
+    let handle = thread::spawn(move || {
+        crossbeam_channel::select! {
+            recv(shutdown) -> msg => msg.is_err(),
+        }
+    });
+
+    pool.close();
+    assert!(handle.join().unwrap());
+}
+
+#[test]
+fn workerless_queue() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(4).no_workers().build();
+    let ran = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..3 {
+        let ran = ran.clone();
+        pool.send_fn(move || {
+            ran.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    }
+
+    assert_eq!(0, pool.stats().workers);
+    assert_eq!(3, pool.queued());
+
+    let consumer = {
+        let pool = pool.clone();
+        thread::spawn(move || pool.iter().map(Job::call).count())
+    };
+
+    pool.send_fn(|| {}).unwrap();
+    pool.close();
+    assert!(pool.is_terminated());
+
+    assert_eq!(4, consumer.join().unwrap());
+    assert_eq!(3, ran.load(Ordering::SeqCst));
+    assert!(pool.recv().is_none());
+}
+
+#[test]
+fn workerless_priorities() {
+    #[derive(Debug, PartialEq)]
+    struct Label(u32);
+
+    impl Job for Label {
+        fn call(self) {}
+    }
+
+    let pool: ThreadPool<Label> = TPBuilder::new().no_workers().unbounded().build();
+
+    pool.send_with_priority(Label(1), Priority::Low).unwrap();
+    pool.send(Label(2)).unwrap();
+    pool.send_with_priority(Label(3), Priority::High).unwrap();
+    assert_eq!(3, pool.queued());
+
+    assert_eq!(Some(Label(3)), pool.steal());
+    assert_eq!(Some(Label(2)), pool.recv());
+
+    pool.send_with_priority(Label(4), Priority::High).unwrap();
+    pool.close();
+    assert_eq!(vec![Label(4), Label(1)], pool.iter().collect::<Vec<_>>());
+    assert_eq!(0, pool.queued());
+}
+
+#[test]
+fn worker_scratch() {
+    let pool = TPBuilder::single()
+        .scratch(|_| Vec::<u32>::with_capacity(16))
+        .build();
+    let (tx, rx) = mpsc::channel();
+
+    for n in 0..3 {
+        let tx = tx.clone();
+        pool.send(move || {
+            assert!(multix::scratch::<String>().is_none());
+
+            let mut buf = multix::scratch::<Vec<u32>>().unwrap();
+            buf.push(n);
+            tx.send(buf.clone()).unwrap();
+        })
+        .unwrap();
+    }
+
+    assert_eq!(vec![0], rx.recv().unwrap());
+    assert_eq!(vec![0, 1], rx.recv().unwrap());
+    assert_eq!(vec![0, 1, 2], rx.recv().unwrap());
+    assert!(multix::scratch::<Vec<u32>>().is_none());
+}
+
+#[test]
+fn worker_state() {
+    struct Connection {
+        worker: usize,
+        queries: usize,
+    }
+
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(2)
+        .mount_with_state(|worker| Connection {
+            worker: worker.index(),
+            queries: 0,
+        })
+        .build();
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..10 {
+        let tx = tx.clone();
+        pool.send_with_state(move |conn: &mut Connection| {
+            conn.queries += 1;
+            tx.send((conn.worker, conn.queries)).unwrap();
+        })
+        .unwrap();
+    }
+    pool.close();
+    pool.await_termination();
+
+    let mut last = [0; 2];
+    for (worker, queries) in rx.try_iter() {
+        assert_eq!(last[worker] + 1, queries);
+        last[worker] = queries;
+    }
+    assert_eq!(10, last[0] + last[1]);
+}
+
+#[test]
+fn submission_breadcrumbs() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().breadcrumbs().build();
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+
+    pool.send_fn(move || {
+        started_tx.send(()).unwrap();
+        release_rx.recv().unwrap();
+    })
+    .unwrap();
+    started_rx.recv().unwrap();
+
+    let sender = {
+        let pool = pool.clone();
+        thread::Builder::new()
+            .name("producer".to_string())
+            .spawn(move || {
+                pool.send_fn(|| {}).unwrap();
+                pool.send_tracked(Box::new(|| {})).unwrap()
+            })
+            .unwrap()
+    };
+    let tracked = sender.join().unwrap();
+
+    let crumbs = pool.queue_snapshot();
+    assert_eq!(2, crumbs.len());
+    assert!(crumbs[0].id < crumbs[1].id);
+    assert_eq!(tracked, crumbs[1].id);
+    for crumb in &crumbs {
+        assert_eq!(Some("producer"), crumb.thread_name.as_deref());
+    }
+
+    release_tx.send(()).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    assert!(pool.queue_snapshot().is_empty());
+    assert_eq!(Some(JobStatus::Completed), pool.status(tracked));
+}
+
+#[test]
+fn breadcrumbs_of_queued_jobs_only() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(1)
+        .queue_capacity(1)
+        .rejection_policy(RejectionPolicy::Abort)
+        .breadcrumbs()
+        .build();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+
+    pool.send_fn(move || {
+        let _ = release_rx.recv();
+    })
+    .unwrap();
+    while pool.stats().active < 1 {
+        thread::yield_now();
+    }
+    assert!(pool.queue_snapshot().is_empty());
+
+    let prepared = pool.prepare(Box::new(|| {}));
+    assert!(pool.queue_snapshot().is_empty());
+
+    pool.send_fn(|| {}).unwrap();
+    assert_eq!(1, pool.queue_snapshot().len());
+    assert!(pool.send_fn(|| {}).is_err());
+    assert!(prepared.commit().is_err());
+    assert_eq!(1, pool.queue_snapshot().len());
+
+    drop(release_tx);
+    pool.close();
+    pool.await_termination();
+    assert!(pool.queue_snapshot().is_empty());
+}
+
+#[test]
+fn seeded_worker_rng() {
+    let draw = || {
+        let pool = TPBuilder::single()
+            .seed_rng(|index| 42 + index as u64)
+            .build();
+        let (tx, rx) = mpsc::channel();
+
+        for _ in 0..2 {
+            let tx = tx.clone();
+            pool.send(move || {
+                let mut rng = multix::rng().unwrap();
+                assert!(multix::rng().is_none());
+                tx.send(rng.next_u64()).unwrap();
+            })
+            .unwrap();
+        }
+
+        (rx.recv().unwrap(), rx.recv().unwrap())
+    };
+
+    let (a, b) = draw();
+    assert_ne!(a, b);
+    assert_eq!((a, b), draw());
+    assert!(multix::rng().is_none());
+}
+
+#[test]
+fn reserved_group() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(3);
+    let (block_tx, block_rx) = mpsc::channel::<()>();
+    let block_rx = Arc::new(Mutex::new(block_rx));
+    let (tx, rx) = mpsc::channel();
+
+    pool.reserve_group("render", 1).unwrap();
+    assert_eq!(
+        Err(GroupTooLarge {
+            requested: 2,
+            available: 1,
+        }),
+        pool.reserve_group("io", 2)
+    );
+    let single: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    assert_eq!(
+        Err(GroupTooLarge {
+            requested: 1,
+            available: 0,
+        }),
+        single.reserve_group("io", 1)
+    );
+
+    for _ in 0..3 {
+        let block_rx = block_rx.clone();
+        pool.send_fn(move || {
+            let _ = block_rx.lock().unwrap().recv();
+        })
+        .unwrap();
+    }
+
+    pool.send_to_group("render", Box::new(move || tx.send("rendered").unwrap()))
+        .unwrap();
+    assert_eq!("rendered", rx.recv().unwrap());
+    assert_eq!(3, pool.stats().workers);
+    assert!(matches!(
+        pool.send_to_group("audio", Box::new(|| {})),
+        Err(SubmitError::UnknownGroup(_))
+    ));
+
+    drop(block_tx);
+    pool.close();
+    pool.await_termination();
+}
+
+#[test]
+fn send_fast() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(2).build();
+    let (tx, rx) = mpsc::channel();
+
+    for n in 0..8 {
+        let tx = tx.clone();
+        pool.send_fast(Box::new(move || tx.send(n).unwrap()))
+            .unwrap();
+    }
+
+    let mut seen: Vec<_> = rx.iter().take(8).collect();
+    seen.sort();
+    assert_eq!((0..8).collect::<Vec<_>>(), seen);
+    assert_eq!(2, pool.stats().workers);
+
+    pool.close();
+    assert!(pool.send_fast(Box::new(|| {})).is_err());
+}
+
+#[test]
+fn prepare_and_commit() {
+    let pool = ThreadPool::new(1);
+    let outcomes = pool.outcomes();
+    let (tx, rx) = mpsc::channel();
+
+    let prepared = pool
+        .prepare(move || tx.send(JobContext::current().is_some()).unwrap())
+        .name("prepared")
+        .context(JobContext::new(1u8))
+        .reporting();
+    let id = prepared.id();
+
+    assert_eq!(0, pool.queued());
+    prepared.commit().unwrap();
+
+    assert!(rx.recv().unwrap());
+
+    let outcome = outcomes.recv().unwrap();
+    assert_eq!(id, outcome.id);
+    assert_eq!(Some("prepared".to_string()), outcome.name);
+}
+
+#[test]
+fn allocator_budget() {
+    let budget = Arc::new(Budget::new(128));
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().allocator(budget.clone()).build();
+    let (tx, rx) = mpsc::channel::<()>();
+    let payload = [0u8; 64];
+
+    pool.send_fn(move || {
+        assert_eq!(64, payload.len());
+        rx.recv().unwrap();
+    })
+    .unwrap();
+    assert!(budget.used() > 64);
+
+    assert!(pool
+        .send_fn(move || assert_eq!(64, payload.len()))
+        .unwrap_err()
+        .is_alloc_refused());
+
+    drop(tx);
+    pool.close();
+    pool.await_termination();
+    assert_eq!(0, budget.used());
+}
+
+#[test]
+#[should_panic(expected = "a sender budget must allow at least one job")]
+fn sender_budget_zero() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let _ = pool.sender().with_budget(0);
+}
+
+#[test]
+fn sender_budget() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(2).build();
+    let sender = pool.sender().with_budget(1);
+    let (tx, rx) = mpsc::channel::<()>();
+
+    sender
+        .send(Box::new(move || {
+            rx.recv().unwrap();
+        }))
+        .unwrap();
+
+    assert_eq!(1, sender.outstanding());
+    assert!(sender.try_acquire().is_none());
+    assert!(sender.clone().try_acquire().is_some());
+
+    thread::scope(|scope| {
+        let waiter = scope.spawn(|| block_on(&pool, sender.acquire_async()));
+
+        thread::sleep(Duration::from_millis(20));
+        assert!(!waiter.is_finished());
+
+        drop(tx);
+        let permit = waiter.join().unwrap();
+        assert_eq!(1, sender.outstanding());
+        permit.send(Box::new(|| {})).unwrap();
+    });
+
+    pool.close();
+    pool.await_termination();
+    assert_eq!(0, sender.outstanding());
+}
+
+#[test]
+fn load_shedding() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(1)
+        .shed(|stats| if stats.queued >= 4 { 0.5 } else { 0.0 })
+        .build();
+    let (tx, rx) = mpsc::channel::<()>();
+
+    pool.send_fn(move || {
+        rx.recv().unwrap();
+    })
+    .unwrap();
+
+    let mut shed = 0;
+    for _ in 0..200 {
+        if let Err(err) = pool.offer(Box::new(|| {})) {
+            assert!(err.is_shed());
+            shed += 1;
+        }
+    }
+
+    assert!(shed > 50 && shed < 150, "shed {} of 200", shed);
+
+    tx.send(()).unwrap();
+    pool.close();
+    assert!(matches!(
+        pool.offer(Box::new(|| {})),
+        Err(SubmitError::ShuttingDown(_))
+    ));
+}
+
+#[test]
+fn job_guard() {
+    #[derive(Default)]
+    struct Counts {
+        enter: AtomicUsize,
+        exit: AtomicUsize,
+        recover: AtomicUsize,
+    }
+
+    struct Guard(Arc<Counts>);
+
+    impl JobGuard for Guard {
+        fn enter(&self) {
+            self.0.enter.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn exit(&self) {
+            self.0.exit.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn recover(&self) {
+            self.0.recover.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let counts = Arc::new(Counts::default());
+    let pool: ThreadPool<fn()> = TPBuilder::single().guard(Guard(counts.clone())).build();
+
+    pool.send(|| {}).unwrap();
+    pool.send(|| panic!("fault")).unwrap();
+    pool.send(|| {}).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(3, counts.enter.load(Ordering::SeqCst));
+    assert_eq!(2, counts.exit.load(Ordering::SeqCst));
+    assert_eq!(1, counts.recover.load(Ordering::SeqCst));
+}
+
+struct Resize(mpsc::Sender<u32>, u32);
+
+struct Encode(bool);
+
+impl Job for Resize {
+    fn call(self) {
+        self.0.send(self.1 * 2).unwrap();
+    }
+}
+
+impl Job for Encode {
+    fn call(self) {
+        assert!(self.0, "encode failed");
+    }
+}
+
+multix::job_enum! {
+    enum Work {
+        Resize(Resize),
+        Encode(Encode),
+    }
+}
+
+#[test]
+fn job_enum_kinds() {
+    let pool: ThreadPool<Work> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::channel();
+
+    pool.send(Work::Resize(Resize(tx.clone(), 4))).unwrap();
+    pool.send(Work::Resize(Resize(tx, 5))).unwrap();
+    pool.send(Work::Encode(Encode(true))).unwrap();
+    pool.send(Work::Encode(Encode(false))).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(vec![8, 10], rx.iter().collect::<Vec<_>>());
+
+    let stats = pool.kind_stats();
+    assert_eq!(2, stats["Resize"].completed);
+    assert_eq!(1, stats["Encode"].completed);
+    assert_eq!(1, stats["Encode"].panicked);
+    assert_eq!(2, stats["Resize"].residency.count());
+    assert!(stats["Encode"].residency.quantile(1.0).is_some());
+}
+
+#[test]
+fn router() {
+    let mut router = Router::new(|job: &Work| job.kind().unwrap());
+    router.add("Resize", ThreadPool::new(2));
+    router.add("Encode", TPBuilder::single().build());
+    let (tx, rx) = mpsc::channel();
+
+    router.send(Work::Resize(Resize(tx, 4))).unwrap();
+    router.send(Work::Encode(Encode(true))).unwrap();
+    assert_eq!(8, rx.recv().unwrap());
+
+    router.close();
+    assert!(router.await_termination_timeout(Duration::from_secs(5)));
+    let stats = router.stats();
+    assert_eq!(
+        vec![&"Resize", &"Encode"],
+        stats.iter().map(|s| s.0).collect::<Vec<_>>()
+    );
+    assert!(stats.iter().all(|s| s.1.lifecycle == Lifecycle::Terminated));
+    assert_eq!(
+        1,
+        router.pool("Encode").unwrap().kind_stats()["Encode"].completed
+    );
+    assert!(router
+        .send(Work::Encode(Encode(true)))
+        .unwrap_err()
+        .is_closed());
+
+    let router: Router<Work, &str> = Router::new(|_| "missing");
+    assert!(router
+        .send(Work::Encode(Encode(true)))
+        .unwrap_err()
+        .is_unrouted());
+}
+
+#[test]
+fn pool_stats_counters() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(2).unbounded().build();
+
+    for i in 0..4 {
+        pool.send_fn(move || assert!(i != 3, "fourth job fails"))
+            .unwrap();
+    }
+    pool.send_with_priority(Box::new(|| {}), Priority::High)
+        .unwrap();
+    pool.close();
+    pool.await_termination();
+
+    let stats = pool.stats();
+    assert_eq!(5, stats.submitted);
+    assert_eq!(4, stats.completed);
+    assert_eq!(1, stats.panicked);
+    assert_eq!((0, 0), (stats.active, stats.idle));
+}
+
+#[test]
+fn pool_dump_workers() {
+    let pool: ThreadPool<Box<dyn JobBox>> =
+        TPBuilder::new().size(2).name("dump").unbounded().build();
+    let (started, wait) = mpsc::channel();
+    let (release, gate) = mpsc::channel::<()>();
+
+    for i in 0..6 {
+        pool.send_fn(move || assert!(i != 5, "last job fails"))
+            .unwrap();
+    }
+    while pool.stats().completed + pool.stats().panicked < 6 {
+        thread::yield_now();
+    }
+    pool.send_fn(move || {
+        started.send(()).unwrap();
+        gate.recv().unwrap();
+    })
+    .unwrap();
+    wait.recv().unwrap();
+
+    let dump = pool.dump();
+    assert_eq!(2, dump.workers.len());
+    assert_eq!(
+        vec![0, 1],
+        dump.workers.iter().map(|w| w.index).collect::<Vec<_>>()
+    );
+    assert_eq!(5, dump.workers.iter().map(|w| w.completed).sum::<u64>());
+    assert_eq!(1, dump.workers.iter().map(|w| w.panicked).sum::<u64>());
+    assert_eq!(
+        1,
+        dump.workers.iter().filter(|w| w.running.is_some()).count()
+    );
+    assert!(dump.workers[0]
+        .thread
+        .as_deref()
+        .unwrap()
+        .starts_with("dump-"));
+    assert_eq!(dump.stats.completed, 5);
+
+    release.send(()).unwrap();
+    pool.close();
+    pool.await_termination();
+}
+
+#[test]
+fn boosted_continuations() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single()
+        .unbounded()
+        .boost_continuations()
+        .build();
+    let (gate, wait) = mpsc::channel::<()>();
+    let (tx, rx) = mpsc::channel();
+
+    let (inner, tx2) = (pool.clone(), tx.clone());
+    pool.send_fn(move || {
+        wait.recv().unwrap();
+        inner
+            .send_fn(move || tx2.send("continuation").unwrap())
+            .unwrap();
+    })
+    .unwrap();
+    pool.send_fn(move || tx.send("external").unwrap()).unwrap();
+    gate.send(()).unwrap();
+
+    assert_eq!(
+        vec!["continuation", "external"],
+        rx.iter().take(2).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn payload_slots() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().slots(2, 1 << 20).build();
+    let (tx, rx) = mpsc::channel();
+
+    let mut first = pool.claim_slot().unwrap();
+    let second = pool.claim_slot().unwrap();
+    assert!(pool.claim_slot().is_none());
+    assert_eq!(0, pool.free_slots());
+
+    first.resize(1 << 20, 7);
+    let addr = first.as_ptr() as usize;
+    pool.send_fn(move || {
+        tx.send((
+            first.as_ptr() as usize,
+            first.iter().map(|&b| b as usize).sum::<usize>(),
+        ))
+        .unwrap();
+    })
+    .unwrap();
+
+    assert_eq!((addr, 7 << 20), rx.recv().unwrap());
+    drop(second);
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(2, pool.free_slots());
+    assert!(pool.claim_slot().unwrap().is_empty());
+}
+
+#[test]
+fn send_after_close() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    let caller = thread::current().id();
+    let (tx, rx) = mpsc::channel();
+
+    assert!(pool.send_or_run_inline(Box::new({
+        let tx = tx.clone();
+        move || tx.send(thread::current().id()).unwrap()
+    })));
+    assert_ne!(caller, rx.recv().unwrap());
+
+    pool.close();
+
+    let job = || -> Box<dyn JobBox> { Box::new(|| {}) };
+    let results = vec![
+        pool.send(job()),
+        pool.send_fn(|| {}),
+        pool.try_send(job()),
+        pool.try_send_fn(|| {}),
+        pool.send_timeout(job(), Duration::from_secs(1)),
+        pool.send_fn_timeout(|| {}, Duration::from_secs(1)),
+        pool.send_fast(job()),
+        pool.send_local(job()),
+        pool.send_critical(job()),
+        pool.send_tracked(job()).map(drop),
+        pool.send_after(job(), Duration::ZERO),
+        pool.send_at(job(), Instant::now()),
+        pool.send_with_priority(job(), Priority::High),
+        pool.send_with_context(job(), JobContext::new(1)),
+        pool.send_reporting(job()).map(drop),
+        pool.send_reporting_named(job(), "late").map(drop),
+        pool.send_to_group("none", job()),
+        pool.send_with_state(|_: &mut u32| {}),
+        pool.send_lazy(|| || {}),
+        pool.prepare(job()).commit(),
+        pool.sender().send(job()),
+        pool.offer(job()),
+    ];
+    for (i, result) in results.into_iter().enumerate() {
+        assert!(result.unwrap_err().is_shutting_down(), "send variant {}", i);
+    }
+
+    assert!(!pool.send_or_run_inline(Box::new(move || {
+        tx.send(thread::current().id()).unwrap()
+    })));
+    assert_eq!(caller, rx.recv().unwrap());
+}
+
+#[test]
+fn inline_job_runs_like_on_worker() {
+    let workers = Arc::new(Mutex::new(Vec::new()));
+    let reported = Arc::new(AtomicUsize::new(0));
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(1)
+        .before_job({
+            let workers = workers.clone();
+            move |meta| workers.lock().unwrap().push((meta.worker, meta.id))
+        })
+        .panic_handler({
+            let reported = reported.clone();
+            move |_| {
+                reported.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .build();
+    pool.close();
+
+    assert!(!pool.send_or_run_inline(Box::new(|| panic!("inline fault"))));
+    // The task the pool refused, the first it made.
+    assert_eq!(vec![(usize::MAX, 0)], *workers.lock().unwrap());
+    assert_eq!(1, reported.load(Ordering::SeqCst));
+    assert_eq!(1, pool.stats().panicked);
+}
+
+#[test]
+fn queue_capacity() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(2).queue_capacity(8).build();
+    let barrier = Arc::new(Barrier::new(3));
+
+    for _ in 0..2 {
+        let barrier = barrier.clone();
+        pool.send_fn(move || {
+            barrier.wait();
+        })
+        .unwrap();
+    }
+    while pool.stats().active < 2 {
+        thread::yield_now();
+    }
+
+    for _ in 0..8 {
+        pool.try_send_fn(|| {}).unwrap();
+    }
+    assert!(pool.try_send_fn(|| {}).is_err());
+
+    barrier.wait();
+}
+
+#[test]
+fn unbounded_queue() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(1).unbounded().build();
+    let (tx, rx) = mpsc::channel::<()>();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    pool.send_fn(move || {
+        let _ = rx.recv();
+    })
+    .unwrap();
+
+    for _ in 0..1000 {
+        let count = count.clone();
+        pool.try_send_fn(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    }
+
+    drop(tx);
+    pool.close();
+    pool.await_termination();
+    assert_eq!(1000, count.load(Ordering::SeqCst));
+}
+
+#[test]
+fn rejection_policies() {
+    let saturated = |policy| {
+        let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+            .size(1)
+            .queue_capacity(1)
+            .rejection_policy(policy)
+            .build();
+        let (tx, rx) = mpsc::channel::<()>();
+
+        pool.send_fn(move || {
+            let _ = rx.recv();
+        })
+        .unwrap();
+        while pool.stats().active < 1 {
+            thread::yield_now();
+        }
+
+        (pool, tx)
+    };
+    let ran = Arc::new(Mutex::new(Vec::new()));
+    let job = |n| {
+        let ran = ran.clone();
+        move || ran.lock().unwrap().push((n, thread::current().id()))
+    };
+
+    let (pool, _release) = saturated(RejectionPolicy::Abort);
+    pool.send_fn(job(1)).unwrap();
+    assert!(pool.send_fn(job(2)).is_err());
+
+    let (pool, _release) = saturated(RejectionPolicy::CallerRuns);
+    pool.send_fn(job(3)).unwrap();
+    pool.send_fn(job(4)).unwrap();
+    assert_eq!(vec![(4, thread::current().id())], *ran.lock().unwrap());
+
+    let (pool, release) = saturated(RejectionPolicy::Discard);
+    pool.send_fn(job(5)).unwrap();
+    pool.send_fn(job(6)).unwrap();
+    drop(release);
+    pool.close();
+    pool.await_termination();
+
+    let (pool, release) = saturated(RejectionPolicy::DiscardOldest);
+    pool.send_fn(job(7)).unwrap();
+    pool.send_fn(job(8)).unwrap();
+    drop(release);
+    pool.close();
+    pool.await_termination();
+
+    let ran: Vec<_> = ran.lock().unwrap().iter().map(|&(n, _)| n).collect();
+    assert_eq!(vec![4, 5, 8], ran);
+
+    // Without a queue there is nothing to discard but the job itself.
+    assert!(TPBuilder::new()
+        .queue_capacity(0)
+        .rejection_policy(RejectionPolicy::DiscardOldest)
+        .try_build::<Box<dyn JobBox>>()
+        .is_err());
+}
+
+#[test]
+fn realtime_producer() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(1).queue_capacity(1).build();
+    let (tx, rx) = mpsc::channel::<()>();
+    let count = Arc::new(AtomicUsize::new(0));
+    let job = || -> Box<dyn JobBox> {
+        let count = count.clone();
+        Box::new(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+        })
+    };
+
+    pool.send_fn(move || {
+        let _ = rx.recv();
+    })
+    .unwrap();
+    pool.send(job()).unwrap();
+
+    let producer = pool.rt_producer(1);
+    producer.send_from_rt(job()).unwrap();
+    let mut next = job();
+    loop {
+        match producer.send_from_rt(next) {
+            Ok(()) => break,
+            Err(err) => next = err.into_inner(),
+        }
+        thread::yield_now();
+    }
+    assert!(producer.send_from_rt(job()).unwrap_err().is_full());
+
+    drop(tx);
+    drop(producer);
+    while count.load(Ordering::SeqCst) < 3 {
+        thread::yield_now();
+    }
+    pool.close();
+    pool.await_termination();
+    assert_eq!(3, count.load(Ordering::SeqCst));
+
+    let producer = pool.rt_producer(4);
+    assert!(producer.send_from_rt(job()).unwrap_err().is_closed());
+}
+
+#[test]
+fn close_force_returns_queued_jobs() {
+    struct Tagged(u32, Option<mpsc::Receiver<()>>);
+
+    impl Job for Tagged {
+        fn call(self) {
+            if let Some(rx) = self.1 {
+                let _ = rx.recv();
+            }
+        }
+    }
+
+    let pool: ThreadPool<Tagged> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::channel();
+
+    pool.send(Tagged(0, Some(rx))).unwrap();
+    while pool.stats().active < 1 {
+        thread::yield_now();
+    }
+    for n in 1..4 {
+        pool.send(Tagged(n, None)).unwrap();
+    }
+
+    let pending: Vec<_> = pool.close_force().into_iter().map(|job| job.0).collect();
+    assert_eq!(vec![1, 2, 3], pending);
+    assert!(pool.close_force().is_empty());
+    drop(tx);
+}
+
+#[test]
+fn deadline_misses() {
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let pool: ThreadPool<Work> = TPBuilder::single()
+        .on_deadline_miss(move |miss| tx.lock().unwrap().send(miss.clone()).unwrap())
+        .build();
+    let (resized, _) = mpsc::channel();
+
+    pool.prepare(Work::Resize(Resize(resized.clone(), 1)))
+        .deadline(Instant::now() + Duration::from_secs(60))
+        .commit()
+        .unwrap();
+    pool.prepare(Work::Resize(Resize(resized, 2)))
+        .name("late")
+        .deadline(Instant::now())
+        .commit()
+        .unwrap();
+    pool.close();
+    pool.await_termination();
+
+    let miss = rx.recv().unwrap();
+    assert_eq!(Some("late".to_string()), miss.name);
+    assert_eq!(Some("Resize"), miss.kind);
+    assert_eq!(1, pool.deadline_misses());
+    assert_eq!(1, pool.kind_stats()["Resize"].deadline_misses);
+}
+
+#[test]
+fn await_termination_gives_up() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::channel::<()>();
+    pool.send_fn(move || {
+        let _ = rx.recv();
+    })
+    .unwrap();
+    pool.close();
+
+    let started = Instant::now();
+    assert!(!pool.await_termination_timeout(Duration::from_millis(20)));
+    assert!(started.elapsed() >= Duration::from_millis(20));
+
+    drop(tx);
+    assert!(pool.await_termination_timeout(Duration::from_secs(5)));
+    assert!(pool.is_terminated());
+}
+
+#[test]
+fn job_priorities() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().unbounded().build();
+    let (gate, wait) = mpsc::channel::<()>();
+    let (tx, rx) = mpsc::channel();
+
+    pool.send_fn(move || wait.recv().unwrap()).unwrap();
+    while pool.stats().active < 1 {
+        thread::yield_now();
+    }
+    for (i, priority) in [Priority::Low, Priority::Normal, Priority::High]
+        .iter()
+        .enumerate()
+    {
+        for j in 0..2 {
+            let tx = tx.clone();
+            let job: Box<dyn JobBox> = Box::new(move || tx.send((*priority, j)).unwrap());
+
+            pool.send_with_priority(job, *priority).unwrap();
+        }
+        assert_eq!(2 * (i + 1), pool.queued());
+    }
+    drop(tx);
+    gate.send(()).unwrap();
+
+    assert_eq!(
+        vec![
+            (Priority::High, 0),
+            (Priority::High, 1),
+            (Priority::Normal, 0),
+            (Priority::Normal, 1),
+            (Priority::Low, 0),
+            (Priority::Low, 1),
+        ],
+        rx.iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn delayed_jobs() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(2);
+    let (tx, rx) = mpsc::channel();
+    let sent = Instant::now();
+
+    let late = tx.clone();
+    pool.send_after(
+        Box::new(move || late.send(("after", Instant::now())).unwrap()),
+        Duration::from_millis(40),
+    )
+    .unwrap();
+    pool.send_at(
+        Box::new(move || tx.send(("at", Instant::now())).unwrap()),
+        sent + Duration::from_millis(20),
+    )
+    .unwrap();
+
+    let (first, at) = rx.recv().unwrap();
+    let (second, after) = rx.recv().unwrap();
+    assert_eq!(("at", "after"), (first, second));
+    assert!(at - sent >= Duration::from_millis(20));
+    assert!(after - sent >= Duration::from_millis(40));
+
+    pool.close();
+    assert!(pool.send_after(Box::new(|| {}), Duration::ZERO).is_err());
+}
+
+#[test]
+fn job_timestamps() {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(2)
+        .record_timestamps(move |t| sink.lock().unwrap().push(*t))
+        .build();
+
+    for _ in 0..4 {
+        pool.send_fn(|| thread::sleep(Duration::from_millis(5)))
+            .unwrap();
+    }
+    pool.close();
+    pool.await_termination();
+
+    let mut records = records.lock().unwrap().clone();
+    records.sort_by_key(|t| t.id);
+    assert_eq!(
+        vec![0, 1, 2, 3],
+        records.iter().map(|t| t.id).collect::<Vec<_>>()
+    );
+    for t in records {
+        assert!(t.enqueued <= t.dequeued);
+        assert!(t.completed - t.dequeued >= Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn per_job_hooks() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let before = events.clone();
+    let after = events.clone();
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(1)
+        .before_job(move |meta| {
+            let name = meta.name.map(str::to_string);
+            before.lock().unwrap().push((meta.id, name, None));
+        })
+        .after_job(move |meta, took| {
+            let name = meta.name.map(str::to_string);
+            after.lock().unwrap().push((meta.id, name, Some(took)));
+        })
+        .build();
+
+    pool.send_reporting_named(Box::new(|| thread::sleep(Duration::from_millis(5))), "nap")
+        .unwrap();
+    pool.send_fn(|| panic!("boom")).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    let events = events.lock().unwrap();
+    let nap = Some("nap".to_string());
+    assert_eq!(4, events.len());
+    assert_eq!((0, nap.clone(), None), events[0]);
+    assert_eq!((0, nap), (events[1].0, events[1].1.clone()));
+    assert!(events[1].2.unwrap() >= Duration::from_millis(5));
+    assert_eq!((1, None, None), events[2]);
+    assert!(events[3].2.is_some());
+}
+
+#[test]
+fn unit_jobs_run_the_handler() {
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let counter = ticks.clone();
+    let pool: ThreadPool<()> = TPBuilder::new()
+        .size(2)
+        .name("ticks")
+        .handler(move |worker| {
+            assert_eq!(Some("ticks"), worker.pool_name());
+            assert!(worker.index() < 2);
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .build();
+
+    for _ in 0..10 {
+        pool.send(()).unwrap();
+    }
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(10, ticks.load(Ordering::SeqCst));
+}
+
+#[test]
+fn job_layers() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let layer = |name: &'static str| {
+        let log = log.clone();
+
+        move |meta: &JobMeta, next: Next| {
+            log.lock().unwrap().push(format!("{} {}", name, meta.id));
+            next.run();
+            log.lock().unwrap().push(format!("/{} {}", name, meta.id));
+        }
+    };
+    let caught = Arc::new(AtomicUsize::new(0));
+    let counter = caught.clone();
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(1)
+        .layer(layer("outer"))
+        .layer(layer("inner"))
+        .layer(move |_: &JobMeta, next: Next| {
+            if panic::catch_unwind(AssertUnwindSafe(|| next.run())).is_err() {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .build();
+
+    let job_log = log.clone();
+    pool.send_fn(move || job_log.lock().unwrap().push("job".to_string()))
+        .unwrap();
+    pool.send_fn(|| panic!("boom")).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(
+        vec!["outer 0", "inner 0", "job", "/inner 0", "/outer 0"],
+        log.lock().unwrap()[..5].to_vec()
+    );
+    assert_eq!(1, caught.load(Ordering::SeqCst));
+    assert_eq!(0, pool.stats().panicked);
+}
+
+#[test]
+fn job_cpu_time() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(1).cpu_time().build();
+    let outcomes = pool.outcomes();
+
+    let spin = Box::new(|| {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(20) {
+            black_box(start);
+        }
+    });
+    pool.send_reporting(spin).unwrap();
+    pool.send_reporting(Box::new(|| thread::sleep(Duration::from_millis(20))))
+        .unwrap();
+
+    let spin = outcomes.recv().unwrap();
+    let sleep = outcomes.recv().unwrap();
+    if cfg!(unix) {
+        assert!(spin.cpu.unwrap() >= Duration::from_millis(10));
+        assert!(sleep.cpu.unwrap() < Duration::from_millis(10));
+    }
+
+    let untimed: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    let outcomes = untimed.outcomes();
+    untimed.send_reporting(Box::new(|| {})).unwrap();
+    assert_eq!(None, outcomes.recv().unwrap().cpu);
+}
+
+#[test]
+fn repeating_jobs() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let start = Instant::now();
+
+    let handle = pool.send_repeating(
+        move || {
+            let tx = tx.lock().unwrap().clone();
+            Box::new(move || tx.send(Instant::now()).unwrap())
+        },
+        Duration::from_millis(10),
+    );
+
+    let ticks: Vec<_> = rx.iter().take(3).collect();
+    assert!(ticks[2] - start >= Duration::from_millis(30));
+
+    handle.cancel();
+    assert!(handle.is_cancelled());
+    thread::sleep(Duration::from_millis(30));
+    while rx.try_recv().is_ok() {}
+    thread::sleep(Duration::from_millis(30));
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn repeating_skips_missed_ticks() {
+    struct Stall;
+
+    impl Wake for Stall {
+        fn wake(self: Arc<Self>) {
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    let ticks = Arc::new(Mutex::new(Vec::new()));
+    let recorded = ticks.clone();
+
+    let handle = pool.send_repeating(
+        move || {
+            recorded.lock().unwrap().push(Instant::now());
+            Box::new(|| {}) as Box<dyn JobBox>
+        },
+        Duration::from_millis(10),
+    );
+
+    // Holds the timer thread up for ten intervals.
+    let mut sleep = pin!(pool.sleep(Duration::from_millis(5)));
+    let waker = Waker::from(Arc::new(Stall));
+    assert!(sleep
+        .as_mut()
+        .poll(&mut Context::from_waker(&waker))
+        .is_pending());
+
+    thread::sleep(Duration::from_millis(200));
+    handle.cancel();
+
+    let ticks = ticks.lock().unwrap();
+    assert!(ticks.len() >= 3);
+    for pair in ticks.windows(2) {
+        assert!(pair[1] - pair[0] >= Duration::from_millis(5));
+    }
+}
+
+fn run_twice<E: Executor>(executor: E, counter: &Arc<AtomicUsize>) {
+    for _ in 0..2 {
+        let counter = counter.clone();
+
+        executor
+            .execute(Box::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }))
+            .unwrap();
+    }
+}
+
+#[test]
+fn executors() {
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    let steps: StepPool<Box<dyn JobBox>> = StepPool::new();
+    run_twice(&steps, &counter);
+    assert_eq!(2, steps.run_until_idle());
+
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(2);
+    run_twice(&pool, &counter);
+    run_twice(pool.sender(), &counter);
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(6, counter.load(Ordering::SeqCst));
+}
+
+#[test]
+fn job_status() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().unbounded().build();
+    let (gate, wait) = mpsc::channel::<()>();
+
+    let first = pool
+        .send_tracked(Box::new(move || wait.recv().unwrap()))
+        .unwrap();
+    while pool.stats().active < 1 {
+        thread::yield_now();
+    }
+    let panics = pool.send_tracked(Box::new(|| panic!("status"))).unwrap();
+    let prepared = pool.prepare(Box::new(|| {})).track();
+    let last = JobId(prepared.id());
+    prepared.commit().unwrap();
+
+    assert_eq!(Some(JobStatus::Running), pool.status(first));
+    assert_eq!(Some(JobStatus::Queued), pool.status(panics));
+    assert_eq!(None, pool.status(JobId(u64::MAX)));
+
+    gate.send(()).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(Some(JobStatus::Completed), pool.status(first));
+    assert_eq!(Some(JobStatus::Panicked), pool.status(panics));
+    assert_eq!(Some(JobStatus::Completed), pool.forget(last));
+    assert_eq!(None, pool.status(last));
+
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().unbounded().build();
+    let (gate, wait) = mpsc::channel::<()>();
+    pool.send_fn(move || wait.recv().unwrap()).unwrap();
+    while pool.stats().active < 1 {
+        thread::yield_now();
+    }
+    let dropped = pool.send_tracked(Box::new(|| {})).unwrap();
+    pool.close_force();
+    drop(gate);
+
+    assert_eq!(Some(JobStatus::Cancelled), pool.status(dropped));
+}
+
+#[test]
+fn step_pool() {
+    let pool: StepPool<Box<dyn JobBox>> = StepPool::new();
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let (first, nested) = (log.clone(), pool.clone());
+    pool.send_fn(move || {
+        first.lock().unwrap().push(1);
+        let log = first.clone();
+        nested.send_fn(move || log.lock().unwrap().push(3)).unwrap();
+    })
+    .unwrap();
+    let second = log.clone();
+    let handle = pool.submit(move || second.lock().unwrap().push(2));
+
+    assert!(log.lock().unwrap().is_empty());
+    assert_eq!(Some(Ok(())), pool.step());
+    assert_eq!(vec![1], *log.lock().unwrap());
+    assert!(!handle.is_finished());
+
+    assert_eq!(2, pool.run_until_idle());
+    assert_eq!(vec![1, 2, 3], *log.lock().unwrap());
+    assert_eq!(Ok(()), handle.join());
+    assert_eq!(None, pool.step());
+
+    pool.close();
+    assert!(pool.send_fn(|| {}).is_err());
+}
+
+#[test]
+fn lazy_jobs_build_on_worker() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    let (tx, rx) = mpsc::channel();
+
+    pool.send_lazy(move || {
+        let payload = vec![1u64; 1024];
+        let worker = thread::current().id();
+
+        move || tx.send((worker, payload.iter().sum::<u64>())).unwrap()
+    })
+    .unwrap();
+
+    let (worker, sum) = rx.recv().unwrap();
+    assert_ne!(thread::current().id(), worker);
+    assert_eq!(1024, sum);
+}
+
+#[test]
+fn blocked_send_fails_on_close() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::from_config(Config {
+        size: 1,
+        queue_capacity: Some(QueueCapacity::Bounded(1)),
+        ..Config::default()
+    });
+    let (tx, rx) = mpsc::channel::<()>();
+
+    pool.send_fn(move || {
+        let _ = rx.recv();
+    })
+    .unwrap();
+    pool.send_fn(|| {}).unwrap();
+
+    let sender = {
+        let pool = pool.clone();
+        thread::spawn(move || pool.send_fn(|| {}).is_err())
+    };
+
+    thread::sleep(Duration::from_millis(20));
+    pool.close();
+    assert!(sender.join().unwrap());
+
+    drop(tx);
+    pool.await_termination();
+}
+
+#[test]
+fn idle_tick() {
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let pool: ThreadPool<fn()> = TPBuilder::fixed(1)
+        .name("ticker")
+        .on_idle_tick(Duration::from_millis(10), move |worker| {
+            let _ = tx
+                .lock()
+                .unwrap()
+                .send((worker.index(), worker.pool_name().map(String::from)));
+        })
+        .build();
+
+    pool.prestart_core_thread();
+
+    for _ in 0..3 {
+        let (index, name) = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(0, index);
+        assert_eq!(Some("ticker".to_string()), name);
+    }
+
+    pool.close();
+    pool.await_termination();
+}
+
+#[test]
+fn steal_jobs() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::channel::<()>();
+    let ran = Arc::new(AtomicUsize::new(0));
+
+    pool.send_fn(move || {
+        rx.recv().unwrap();
+    })
+    .unwrap();
+
+    for _ in 0..5 {
+        let ran = ran.clone();
+        pool.send_fn(move || {
+            ran.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    }
+
+    thread::sleep(Duration::from_millis(20));
+    let stolen = pool.steal_batch(3);
+    assert_eq!(3, stolen.len());
+    stolen.into_iter().for_each(Job::call);
+    pool.steal().unwrap().call();
+    assert_eq!(4, ran.load(Ordering::SeqCst));
+    assert_eq!(1, pool.queued());
+
+    tx.send(()).unwrap();
+    pool.close();
+    pool.await_termination();
+    assert_eq!(5, ran.load(Ordering::SeqCst));
+    assert!(pool.steal().is_none());
+}
+
+#[test]
+fn submit_returns_result() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(2);
+    let (tx, rx) = mpsc::channel::<()>();
+
+    let sum = pool.submit(|| (1..=10).sum::<u32>());
+    assert_eq!(Ok(55), sum.join());
+
+    let slow = pool.submit(move || rx.recv().map(|_| "done"));
+    assert!(slow.try_join().is_none());
+    assert!(slow.join_timeout(Duration::from_millis(10)).is_none());
+    tx.send(()).unwrap();
+    assert_eq!(
+        Some(Ok(Ok("done"))),
+        slow.join_timeout(Duration::from_secs(5))
+    );
+    assert!(slow.is_finished());
+    assert_eq!(Some(Err(JobError::Taken)), slow.try_join());
+    assert_eq!(Err(JobError::Taken), slow.join());
+
+    let failed = pool.submit(|| -> u32 { panic!("bad input") });
+    assert_eq!(
+        Err(JobError::Panicked("bad input".to_string())),
+        failed.join()
+    );
+
+    pool.close();
+    assert_eq!(Err(JobError::Cancelled), pool.submit(|| 1).join());
+}
+
+#[test]
+fn disconnect_terminates_pool() {
+    let (tx, rx) = mpsc::channel();
+    let events = Mutex::new(tx);
+    let unmounted = Arc::new(AtomicUsize::new(0));
+    let pool: ThreadPool<fn()> = TPBuilder::fixed(2)
+        .unmount({
+            let unmounted = unmounted.clone();
+            move |_| {
+                unmounted.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .on_disconnect(move || events.lock().unwrap().send("disconnected").unwrap())
+        .build();
+
+    pool.prestart_core_threads();
+    assert_eq!(2, pool.stats().workers);
+    drop(pool);
+
+    assert_eq!(
+        "disconnected",
+        rx.recv_timeout(Duration::from_secs(1)).unwrap()
+    );
+    assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    assert_eq!(2, unmounted.load(Ordering::SeqCst));
+}
+
+#[test]
+fn respawn_after_hook_panic() {
+    struct Faulty;
+
+    impl JobGuard for Faulty {
+        fn exit(&self) {
+            if JobContext::current().is_some() {
+                panic!("guard fault");
+            }
+        }
+    }
+
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(1).guard(Faulty).build();
+    let (tx, rx) = mpsc::channel();
+
+    let first = tx.clone();
+    pool.send_with_context(
+        Box::new(move || first.send(thread::current().id()).unwrap()),
+        JobContext::new(()),
+    )
+    .unwrap();
+    let dead = rx.recv().unwrap();
+
+    pool.send_fn(move || tx.send(thread::current().id()).unwrap())
+        .unwrap();
+    assert_ne!(dead, rx.recv().unwrap());
+
+    assert_eq!(1, pool.stats().workers);
+
+    pool.close();
+    pool.await_termination();
+    assert_eq!(0, pool.stats().active);
+}
+
+#[test]
+fn respawn_budget_for_failing_hook() {
+    let reported = Arc::new(AtomicUsize::new(0));
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(2)
+        .spawn_policy(SpawnPolicy::Eager)
+        .mount(|_| panic!("mount fault"))
+        .panic_handler({
+            let reported = reported.clone();
+            move |_| {
+                reported.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .build();
+
+    // Both workers, then eight replacements.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while reported.load(Ordering::SeqCst) < 10 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    thread::sleep(Duration::from_millis(100));
+
+    assert_eq!(10, reported.load(Ordering::SeqCst));
+    assert_eq!(0, pool.stats().workers);
+}
+
+#[test]
+fn select_first_handle() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let other: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::channel::<()>();
+    let skipped = Arc::new(AtomicUsize::new(0));
+
+    let slow = pool.submit(move || {
+        let _ = rx.recv();
+        "slow"
+    });
+    let fast = other.submit(|| {
+        thread::sleep(Duration::from_millis(10));
+        "fast"
+    });
+    let queued = {
+        let skipped = skipped.clone();
+        pool.submit(move || {
+            skipped.fetch_add(1, Ordering::SeqCst);
+            "queued"
+        })
+        .on_drop(OnDrop::Cancel)
+    };
+
+    let (index, result) = multix::select(vec![slow, fast, queued]);
+    assert_eq!((1, Ok("fast")), (index, result));
+
+    drop(tx);
+    pool.close();
+    pool.await_termination();
+    assert_eq!(0, skipped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn join_all_handles() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(4).build();
+
+    let handles = (0..4u64).map(|n| {
+        pool.submit(move || {
+            thread::sleep(Duration::from_millis(5 * (4 - n)));
+            n * n
+        })
+    });
+    let results: Vec<_> = multix::join_all(handles)
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(vec![0, 1, 4, 9], results);
+
+    let (tx, rx) = mpsc::channel::<()>();
+    let handles = vec![
+        pool.submit(|| 1),
+        pool.submit(move || {
+            let _ = rx.recv();
+            2
+        }),
+    ];
+    let results = multix::join_all_timeout(handles, Duration::from_millis(50));
+    assert_eq!(vec![Some(Ok(1)), None], results);
+    drop(tx);
+}
+
+#[test]
+fn job_progress() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+
+    let (handle, progress) = pool.send_with_progress(|sink| {
+        for step in 1..=4 {
+            sink.report(step * 25);
+        }
+        "done"
+    });
+
+    assert_eq!(vec![25, 50, 75, 100], progress.iter().collect::<Vec<_>>());
+    assert_eq!(Ok("done"), handle.join());
+    assert_eq!(None, progress.recv());
+}
+
+#[test]
+fn progress_summary() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::channel::<()>();
+    let (started_tx, started) = mpsc::channel();
+
+    let (first, _) = pool.send_with_progress(move |sink: &multix::ProgressSink<()>| {
+        sink.set_fraction(0.5);
+        started_tx.send(()).unwrap();
+        let _ = rx.recv();
+    });
+    let second = pool.submit(|| {});
+    started.recv().unwrap();
+
+    let summary = pool.progress_summary();
+    assert_eq!(
+        (1, 1, 0),
+        (summary.queued, summary.in_flight, summary.completed)
+    );
+    assert_eq!(vec![0.5], summary.fractions);
+    assert_eq!(0.25, summary.fraction());
+
+    drop(tx);
+    multix::join_all(vec![first, second]);
+    pool.close();
+    pool.await_termination();
+
+    let summary = pool.progress_summary();
+    assert_eq!(2, summary.completed);
+    assert!(summary.fractions.is_empty());
+    assert_eq!(1.0, summary.fraction());
+}
+
+#[test]
+fn panic_handler() {
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let pool: ThreadPool<fn()> = TPBuilder::single()
+        .panic_handler(move |payload| {
+            let message = payload.downcast::<&str>().map(|message| *message);
+            tx.lock().unwrap().send(message.ok()).unwrap();
+        })
+        .build();
+
+    pool.send(|| {}).unwrap();
+    pool.send(|| panic!("job failed")).unwrap();
+    pool.send(|| {}).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(vec![Some("job failed")], rx.try_iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn scoped_jobs_borrow() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(4).build();
+    let data: Vec<usize> = (1..=100).collect();
+    let sum = AtomicUsize::new(0);
+
+    let chunks = pool.scope(|s| {
+        let sum = &sum;
+
+        for chunk in data.chunks(10) {
+            s.send(move || {
+                sum.fetch_add(chunk.iter().sum(), Ordering::SeqCst);
+            });
+        }
+
+        data.chunks(10).count()
+    });
+
+    assert_eq!(10, chunks);
+    assert_eq!(5050, sum.load(Ordering::SeqCst));
+
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        pool.scope(|s| s.send(|| panic!("scoped job failed")));
+    }));
+    assert!(panicked.is_err());
+    assert_eq!(4, pool.stats().workers);
+}
+
+#[test]
+fn fork_join() {
+    fn sum(pool: &ThreadPool<Box<dyn JobBox>>, data: &[u64]) -> u64 {
+        if data.len() <= 8 {
+            return data.iter().sum();
+        }
+
+        let (left, right) = data.split_at(data.len() / 2);
+        let (a, b) = pool.join(|| sum(pool, left), || sum(pool, right));
+
+        a + b
+    }
+
+    // Two workers and room for two queued jobs, far fewer than the joins
+    // waiting at once.
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(2).build();
+    let data: Vec<u64> = (1..=1000).collect();
+    assert_eq!(500500, sum(&pool, &data));
+
+    let (tx, rx) = mpsc::channel();
+    {
+        let pool = pool.clone();
+        pool.clone()
+            .send_fn(move || tx.send(sum(&pool, &data)).unwrap())
+            .unwrap();
+    }
+    assert_eq!(500500, rx.recv_timeout(Duration::from_secs(10)).unwrap());
+
+    // Jobs splitting themselves through the scope.
+    fn split<'a>(s: &'a multix::Scope<'a, '_>, count: &'a AtomicUsize, depth: u32) {
+        count.fetch_add(1, Ordering::SeqCst);
+
+        if depth > 0 {
+            s.send(move || split(s, count, depth - 1));
+            s.send(move || split(s, count, depth - 1));
+        }
+    }
+
+    let count = AtomicUsize::new(0);
+    pool.scope(|s| split(s, &count, 6));
+    assert_eq!(127, count.load(Ordering::SeqCst));
+}
+
+#[test]
+fn scope_on_discarding_pool() {
+    for policy in [RejectionPolicy::Discard, RejectionPolicy::DiscardOldest] {
+        let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+            .size(1)
+            .queue_capacity(1)
+            .rejection_policy(policy)
+            .build();
+        let (release, gate) = mpsc::channel::<()>();
+        pool.send_fn(move || {
+            let _ = gate.recv();
+        })
+        .unwrap();
+        while pool.stats().active < 1 {
+            thread::yield_now();
+        }
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            release.send(()).unwrap();
+        });
+
+        // The queue holds one job and the worker is busy, so the pool drops
+        // the rest, which then run on this thread.
+        let count = AtomicUsize::new(0);
+        let joined = pool.scope(|s| {
+            for _ in 0..8 {
+                s.send(|| {
+                    count.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+
+            pool.join(|| 1, || 2)
+        });
+        assert_eq!(8, count.load(Ordering::SeqCst));
+        assert_eq!((1, 2), joined);
+    }
+}
+
+#[test]
+fn region_collects_errors() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let (tx, rx) = mpsc::channel::<()>();
+    let (started_tx, started) = mpsc::channel::<()>();
+    let ran = AtomicUsize::new(0);
+
+    let result = pool.region(|r| {
+        let ran = &ran;
+
+        r.spawn(move || {
+            started_tx.send(()).unwrap();
+            rx.recv().unwrap();
+            ran.fetch_add(1, Ordering::SeqCst);
+        });
+        started.recv().unwrap();
+        r.spawn(|| panic!("region job failed"));
+        r.spawn(move || {
+            ran.fetch_add(1, Ordering::SeqCst);
+        });
+        r.cancel();
+        tx.send(()).unwrap();
+    });
+
+    assert_eq!(1, ran.load(Ordering::SeqCst));
+    assert_eq!(1, result.completed);
+    assert_eq!(
+        vec![JobError::Cancelled, JobError::Cancelled],
+        result.errors
+    );
+
+    let result = pool.region(|r| {
+        r.spawn(|| {});
+        r.spawn(|| panic!("region job failed"));
+    });
+    assert_eq!(1, result.completed);
+    assert_eq!(
+        vec![JobError::Panicked("region job failed".to_string())],
+        result.errors
+    );
+}
+
+#[test]
+fn region_panic_policies() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::single().build();
+    let panicked = || JobError::Panicked("region job failed".to_string());
+
+    let result = pool.region_with(PanicPolicy::CancelRemaining, |r| {
+        r.spawn(|| panic!("region job failed"));
+        r.spawn(|| {});
+    });
+    assert_eq!(0, result.completed);
+    assert_eq!(vec![panicked(), JobError::Cancelled], result.errors);
+    assert!(!result.poisoned);
+
+    let result = pool.region_with(PanicPolicy::Poison, |r| {
+        r.spawn(|| panic!("region job failed"));
+        while !r.is_poisoned() {
+            thread::yield_now();
+        }
+        r.spawn(|| {});
+    });
+    assert_eq!(0, result.completed);
+    assert_eq!(vec![panicked(), JobError::Cancelled], result.errors);
+    assert!(result.poisoned);
+}
+
+#[test]
+fn child_pools_close_with_parent() {
+    let stage: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(2).build();
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(2).build();
+    let count = Arc::new(AtomicUsize::new(0));
+    pool.adopt(&stage);
+
+    for _ in 0..8 {
+        let stage = stage.clone();
+        let count = count.clone();
+        pool.send_fn(move || {
+            thread::sleep(Duration::from_millis(5));
+            stage
+                .send_fn(move || {
+                    count.fetch_add(1, Ordering::SeqCst);
+                })
+                .unwrap();
+        })
+        .unwrap();
+    }
+
+    pool.close();
+    assert!(!stage.is_terminating());
+    pool.await_termination();
+
+    assert!(pool.is_terminated());
+    assert!(stage.is_terminated());
+    assert_eq!(8, count.load(Ordering::SeqCst));
+}
+
+#[test]
+fn pool_stats() {
+    let pool = ThreadPool::new(2);
+    let (tx, rx) = mpsc::sync_channel(0);
+
+    pool.send(move || {
+        rx.recv().unwrap();
+    })
+    .unwrap();
+
+    let stats = pool.stats();
+    assert_eq!(Lifecycle::Running, stats.lifecycle);
+    assert_eq!(2, stats.size);
+    assert_eq!(1, stats.workers);
+
+    let debug = format!("{:?}", pool);
+    assert!(debug.contains("lifecycle: Running"));
+    assert!(debug.contains("workers: 1"));
+
+    tx.send(()).unwrap();
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(Lifecycle::Terminated, pool.stats().lifecycle);
+}
+
+#[test]
+fn spawn_policies() {
+    let eager: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(3)
+        .spawn_policy(SpawnPolicy::Eager)
+        .build();
+    assert_eq!(3, eager.stats().workers);
+
+    let manual: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(3)
+        .unbounded()
+        .spawn_policy(SpawnPolicy::Manual)
+        .build();
+    let (tx, rx) = mpsc::channel();
+
+    for i in 0..4 {
+        let tx = tx.clone();
+        manual.send_fn(move || tx.send(i).unwrap()).unwrap();
+    }
+    assert_eq!(0, manual.stats().workers);
+    assert_eq!(4, manual.queued());
+
+    assert!(manual.prestart_core_thread());
+    let mut ran: Vec<i32> = rx.iter().take(4).collect();
+    ran.sort();
+    assert_eq!(vec![0, 1, 2, 3], ran);
+    assert_eq!(1, manual.stats().workers);
+
+    eager.close();
+    manual.close();
+    eager.await_termination();
+    manual.await_termination();
+}
+
+#[test]
+fn close_runs_jobs_of_manual_pool() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new()
+        .size(2)
+        .unbounded()
+        .spawn_policy(SpawnPolicy::Manual)
+        .build();
+    let ran = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..3 {
+        let ran = ran.clone();
+        pool.send_fn(move || {
+            ran.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    }
+    assert_eq!(0, pool.stats().workers);
+
+    pool.close();
+    assert!(pool.await_termination_timeout(Duration::from_secs(5)));
+    assert_eq!(3, ran.load(Ordering::SeqCst));
+    assert_eq!(0, pool.queued());
+}
+
 // #[test]
 // fn threads_shutdown_now() {
 //     let pool = ThreadPool::single_thread();
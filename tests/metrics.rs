@@ -0,0 +1,126 @@
+#![cfg(feature = "metrics")]
+
+extern crate multix;
+
+use metrics::{
+    Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+use multix::core::TPBuilder;
+use multix::{JobBox, ThreadPool};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// Keeps the last value of every gauge, the total of every counter and the
+// number of samples of every histogram, keyed by `<name>/<pool>`.
+#[derive(Clone, Default)]
+struct Values(Arc<Mutex<HashMap<String, f64>>>);
+
+struct Metric {
+    key: String,
+    values: Values,
+}
+
+impl Metric {
+    fn update(&self, f: impl FnOnce(&mut f64)) {
+        f(self
+            .values
+            .0
+            .lock()
+            .unwrap()
+            .entry(self.key.clone())
+            .or_default())
+    }
+}
+
+impl CounterFn for Metric {
+    fn increment(&self, value: u64) {
+        self.update(|v| *v += value as f64)
+    }
+
+    fn absolute(&self, value: u64) {
+        self.update(|v| *v = value as f64)
+    }
+}
+
+impl GaugeFn for Metric {
+    fn increment(&self, value: f64) {
+        self.update(|v| *v += value)
+    }
+
+    fn decrement(&self, value: f64) {
+        self.update(|v| *v -= value)
+    }
+
+    fn set(&self, value: f64) {
+        self.update(|v| *v = value)
+    }
+}
+
+impl HistogramFn for Metric {
+    fn record(&self, _: f64) {
+        self.update(|v| *v += 1.0)
+    }
+}
+
+impl Values {
+    fn metric(&self, key: &Key) -> Arc<Metric> {
+        let pool = key
+            .labels()
+            .find(|label| label.key() == "pool")
+            .map_or("", |label| label.value());
+
+        Arc::new(Metric {
+            key: format!("{}/{}", key.name(), pool),
+            values: self.clone(),
+        })
+    }
+
+    fn get(&self, key: &str) -> f64 {
+        self.0.lock().unwrap().get(key).copied().unwrap_or_default()
+    }
+}
+
+impl Recorder for Values {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        Counter::from_arc(self.metric(key))
+    }
+
+    fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+        Gauge::from_arc(self.metric(key))
+    }
+
+    fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+        Histogram::from_arc(self.metric(key))
+    }
+}
+
+#[test]
+fn pool_metrics() {
+    let values = Values::default();
+    metrics::set_global_recorder(values.clone()).unwrap();
+
+    let named: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().name("io").size(2).build();
+    let unnamed: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(1).build();
+
+    for _ in 0..10 {
+        named.send_fn(|| {}).unwrap();
+    }
+    named.send_fn(|| panic!("boom")).unwrap();
+    unnamed.send_fn(|| {}).unwrap();
+
+    named.close();
+    named.await_termination();
+    unnamed.close();
+    unnamed.await_termination();
+
+    assert_eq!(10.0, values.get("multix_jobs_completed_total/io"));
+    assert_eq!(1.0, values.get("multix_jobs_panicked_total/io"));
+    assert_eq!(11.0, values.get("multix_job_wait_seconds/io"));
+    assert_eq!(11.0, values.get("multix_job_run_seconds/io"));
+    assert_eq!(1.0, values.get("multix_jobs_completed_total/multix"));
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "mutex-state")]
+
+extern crate multix;
+
+use multix::atomic::AtomicState;
+use multix::core::TPBuilder;
+use multix::lifecycle::Lifecycle;
+use multix::{JobBox, ThreadPool};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn lifecycle_transitions() {
+    let state = AtomicState::new(Lifecycle::Running);
+    assert_eq!(Lifecycle::Running, state.load().lifecycle());
+
+    assert!(state.try_transition_to_shutdown());
+    assert!(!state.try_transition_to_shutdown());
+    assert!(state.try_transition_to_stop());
+    assert!(!state.try_transition_to_shutdown());
+    assert!(state.try_transition_to_tidying());
+    assert!(!state.try_transition_to_stop());
+
+    state.transition_to_terminated();
+    assert!(state.load().is_terminated());
+    assert!(!state.try_transition_to_tidying());
+}
+
+#[test]
+fn worker_count_transitions() {
+    let state = AtomicState::new(Lifecycle::Running);
+    let start = state.load();
+
+    assert!(state.compare_and_inc_worker_count(start).is_ok());
+    assert_eq!(1, state.load().worker_count());
+
+    let stale = state.compare_and_inc_worker_count(start).err().unwrap();
+    assert_eq!(1, stale.worker_count());
+    assert!(state.compare_and_inc_worker_count(stale).is_ok());
+    assert_eq!(2, state.load().worker_count());
+
+    assert!(!state.compare_and_dec_worker_count(stale));
+    assert!(state.compare_and_dec_worker_count(state.load()));
+    assert_eq!(1, state.load().worker_count());
+
+    assert!(state.try_transition_to_shutdown());
+    assert_eq!(1, state.load().worker_count());
+    assert_eq!(1, state.fetch_dec_worker_count().worker_count());
+    assert_eq!(0, state.load().worker_count());
+    assert_eq!(Lifecycle::Shutdown, state.load().lifecycle());
+
+    assert!(state.compare_and_inc_worker_count(state.load()).is_ok());
+    assert!(state.compare_and_inc_worker_count(state.load()).is_ok());
+    state.reset_worker_count();
+    assert_eq!(0, state.load().worker_count());
+    assert_eq!(Lifecycle::Shutdown, state.load().lifecycle());
+}
+
+#[test]
+fn concurrent_worker_counts() {
+    let state = Arc::new(AtomicState::new(Lifecycle::Running));
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let state = state.clone();
+
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    let mut current = state.load();
+
+                    while let Err(actual) = state.compare_and_inc_worker_count(current) {
+                        current = actual;
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(8000, state.load().worker_count());
+    assert_eq!(Lifecycle::Running, state.load().lifecycle());
+}
+
+#[test]
+fn pool_lifecycle() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::fixed(1).build();
+    let barrier = Arc::new(Barrier::new(5));
+    let ran = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..4 {
+        let barrier = barrier.clone();
+        let ran = ran.clone();
+
+        pool.send_fn(move || {
+            barrier.wait();
+            ran.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    }
+
+    pool.resize(4).unwrap();
+    barrier.wait();
+    assert_eq!(4, pool.stats().workers);
+
+    pool.resize(2).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pool.stats().workers > 2 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(2, pool.stats().workers);
+
+    pool.close();
+    assert!(pool.send_fn(|| {}).is_err());
+    assert!(pool.await_termination_timeout(Duration::from_secs(5)));
+    assert!(pool.is_terminated());
+    assert_eq!(4, ran.load(Ordering::SeqCst));
+    assert_eq!(0, pool.stats().workers);
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "smol")]
+
+extern crate executor_trait;
+extern crate multix;
+
+use executor_trait::BlockingExecutor;
+use multix::{JobBox, ThreadPool};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake};
+use std::thread::{self, Thread};
+
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Arc::new(Unpark(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+fn run_on(executor: &dyn BlockingExecutor) -> Option<thread::ThreadId> {
+    let ran = Arc::new(Mutex::new(None));
+    let id = ran.clone();
+
+    block_on(executor.spawn_blocking(Box::new(move || {
+        *id.lock().unwrap() = Some(thread::current().id());
+    })));
+
+    let id = *ran.lock().unwrap();
+    id
+}
+
+#[test]
+fn spawn_blocking_on_pool() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(2);
+
+    let id = run_on(&pool).unwrap();
+
+    assert_ne!(thread::current().id(), id);
+}
+
+#[test]
+fn refused_job_runs_on_caller() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+    pool.close();
+
+    assert_eq!(Some(thread::current().id()), run_on(&pool));
+}
+
+#[test]
+fn panic_resumes_in_caller() {
+    let pool: ThreadPool<Box<dyn JobBox>> = ThreadPool::new(1);
+
+    let err = panic::catch_unwind(AssertUnwindSafe(|| {
+        block_on(pool.spawn_blocking(Box::new(|| panic!("boom"))))
+    }))
+    .unwrap_err();
+
+    assert_eq!(Some(&"boom".to_string()), err.downcast_ref::<String>());
+}
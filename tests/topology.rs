@@ -0,0 +1,82 @@
+#![cfg(feature = "topology")]
+
+extern crate multix;
+
+use multix::core::TPBuilder;
+use multix::topology::Topology;
+use multix::{JobBox, ThreadPool};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn detected_topology() {
+    let topology = Topology::detect();
+    let mut ids: Vec<_> = topology.cpus.iter().map(|cpu| cpu.id).collect();
+
+    assert!(!ids.is_empty());
+    assert!(topology.physical_cores() >= 1);
+    assert!(topology.physical_cores() <= ids.len());
+
+    for cpu in &topology.cpus {
+        assert!(cpu.siblings.contains(&cpu.id));
+        assert_eq!(cpu.node, topology.node_of(cpu.id));
+        if let Some(node) = cpu.node {
+            assert!(topology.nodes[node].contains(&cpu.id));
+        }
+    }
+
+    for cache in &topology.caches {
+        assert!(cache.level >= 1);
+        assert!(!cache.cpus.is_empty());
+    }
+
+    let mut spread = topology.spread();
+    let cores = topology.physical_cores();
+    let first: Vec<_> = spread[..cores]
+        .iter()
+        .map(|&id| {
+            let cpu = topology.cpus.iter().find(|cpu| cpu.id == id).unwrap();
+            (cpu.package, cpu.core)
+        })
+        .collect();
+    let mut distinct = first.clone();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(first.len(), distinct.len());
+
+    spread.sort();
+    ids.sort();
+    assert_eq!(ids, spread);
+}
+
+#[test]
+fn numa_pool() {
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(4).numa().build();
+    let ran = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..50 {
+        let ran = ran.clone();
+        let inner = pool.clone();
+
+        pool.send_fn(move || {
+            ran.fetch_add(1, Ordering::SeqCst);
+            inner
+                .send_fn(move || {
+                    ran.fetch_add(1, Ordering::SeqCst);
+                })
+                .unwrap();
+        })
+        .unwrap();
+    }
+
+    while ran.load(Ordering::SeqCst) < 100 {
+        thread::sleep(Duration::from_millis(1));
+    }
+    pool.close();
+    pool.await_termination();
+
+    assert_eq!(0, pool.queued());
+    assert_eq!(100, pool.stats().completed);
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "tracing")]
+
+extern crate multix;
+
+use multix::core::TPBuilder;
+use multix::{JobBox, ThreadPool};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use tracing_core::span::Current;
+
+thread_local! {
+    static ENTERED: RefCell<Vec<Id>> = const { RefCell::new(Vec::new()) };
+}
+
+// An event's message and the span it was emitted in.
+type Recorded = (String, Option<&'static str>);
+
+// Keeps track of entered spans per thread and records every event.
+#[derive(Clone, Default)]
+struct Spans {
+    next: Arc<AtomicU64>,
+    spans: Arc<Mutex<HashMap<u64, &'static Metadata<'static>>>>,
+    events: Arc<Mutex<Vec<Recorded>>>,
+}
+
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next.fetch_add(1, Ordering::Relaxed) + 1;
+        self.spans.lock().unwrap().insert(id, span.metadata());
+
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = Message(String::new());
+        event.record(&mut message);
+
+        let span = self.current_span().metadata().map(|meta| meta.name());
+        self.events.lock().unwrap().push((message.0, span));
+    }
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.clone()));
+    }
+
+    fn exit(&self, _: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().pop());
+    }
+
+    fn current_span(&self) -> Current {
+        match ENTERED.with(|entered| entered.borrow().last().cloned()) {
+            Some(id) => Current::new(id.clone(), self.spans.lock().unwrap()[&id.into_u64()]),
+            None => Current::none(),
+        }
+    }
+}
+
+#[test]
+fn jobs_run_in_the_sending_span() {
+    let spans = Spans::default();
+    tracing::subscriber::set_global_default(spans.clone()).unwrap();
+
+    let pool: ThreadPool<Box<dyn JobBox>> = TPBuilder::new().size(1).build();
+
+    tracing::info_span!("request").in_scope(|| {
+        pool.send_fn(|| tracing::info!("working")).unwrap();
+    });
+    pool.send_fn(|| tracing::info!("unscoped")).unwrap();
+
+    pool.close();
+    pool.await_termination();
+
+    let events = spans.events.lock().unwrap();
+    let find = |message: &str| {
+        events
+            .iter()
+            .filter(|(m, _)| m == message)
+            .map(|(_, span)| *span)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(vec![Some("request")], find("working"));
+    assert_eq!(vec![None], find("unscoped"));
+    assert_eq!(vec![Some("request"), None], find("job enqueued"));
+    assert_eq!(vec![Some("request"), None], find("job dequeued"));
+    assert_eq!(vec![Some("request"), None], find("job completed"));
+}